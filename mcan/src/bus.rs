@@ -1,24 +1,36 @@
 //! Pad declarations for the CAN buses
 
-use crate::config::{BitTimingError, DATA_BIT_TIMING_RANGES, NOMINAL_BIT_TIMING_RANGES};
-use crate::filter::{FiltersExtended, FiltersStandard};
-use crate::interrupt::{state, InterruptConfiguration, OwnedInterruptSet};
+#[cfg(not(feature = "no-fd-config"))]
+use crate::config::{check_fd_timing, FdTimingIssue, DATA_BIT_TIMING_RANGES};
+use crate::config::{
+    BitTimingError, GlobalFilter, RawNominalTiming, Spin, TimeStampSelect,
+    NOMINAL_BIT_TIMING_RANGES,
+};
+use crate::filter::{FiltersExtended, FiltersStandard, NonMatchingAction};
+use crate::interrupt::{
+    state, Interrupt, InterruptConfiguration, InterruptLine, InterruptSet, MaskError,
+    OwnedInterruptSet,
+};
 use crate::messageram::SharedMemoryInner;
-use crate::reg::{ecr::R as ECR, psr::R as PSR};
+use crate::reg::gfc::{ANFESELECT_A, ANFSSELECT_A};
+use crate::reg::{cccr::R as CCCR, cust::R as CUST, ecr::R as ECR, psr::R as PSR};
 use crate::rx_dedicated_buffers::RxDedicatedBuffer;
-use crate::rx_fifo::{Fifo0, Fifo1, RxFifo};
+use crate::rx_fifo::{Fifo0, Fifo1, RxFifo, RxFrameSource};
 use crate::tx_buffers::Tx;
-use crate::tx_event_fifo::TxEventFifo;
+use crate::tx_event_fifo::{DynTxEventFifo, TxEventFifo};
+use core::cell::Cell;
 use core::convert::From;
 use core::fmt::{self, Debug};
+use core::marker::PhantomData;
 use core::ops::Deref;
+use core::ptr::NonNull;
 
 use super::{
-    config::{CanConfig, Mode},
-    message::AnyMessage,
+    config::{BitTiming, CanConfig, Mode, Timestamp},
+    message::{AnyMessage, TxEvent},
     messageram::{Capacities, SharedMemory},
 };
-use fugit::HertzU32;
+use fugit::{HertzU32, MicrosDurationU32};
 use generic_array::typenum::Unsigned;
 
 /// Wrapper for the protocol status register
@@ -56,9 +68,269 @@ impl Debug for ProtocolStatus {
     }
 }
 
+impl ProtocolStatus {
+    /// Type of the last error that occurred on the bus
+    pub fn last_error_code(&self) -> LastErrorCode {
+        self.lec().variant().into()
+    }
+
+    /// Transmitter delay measured by the transmitter delay compensation, in
+    /// minimum time quanta (PSR.TDCV)
+    ///
+    /// When transmitter delay compensation is enabled (DBTP.TDC), the
+    /// secondary sample point used to check transmitted bits in the data
+    /// phase is placed at this delay plus the offset configured in TDCR.TDCO.
+    /// If the offset does not fit the delay of the transceiver, e.g. because
+    /// the sum exceeds the data bit time, transmitted FD frames fail with bit
+    /// or CRC errors reported in [`FdStatus::data_last_error_code`]. The value
+    /// is only updated while transmitting bit rate switched frames.
+    pub fn transmitter_delay(&self) -> u8 {
+        self.tdcv().bits()
+    }
+
+    /// CAN FD specific fields, useful to diagnose problems in the data phase
+    pub fn fd_status(&self) -> FdStatus {
+        FdStatus {
+            data_last_error_code: self.dlec().variant().into(),
+            error_state_indicator: self.resi().bit(),
+            bit_rate_switch: self.rbrs().bit(),
+            fd_frame: self.rfdf().bit(),
+        }
+    }
+}
+
+/// Fields of the protocol status register that are reset when it is read,
+/// accumulated in software since they were last taken
+///
+/// See [`DynAux::protocol_status_peek`] and [`DynAux::protocol_status_take`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StickyStatus {
+    /// Type of the last error that occurred on the bus (LEC), or
+    /// [`LastErrorCode::NoChange`] if there was no bus event
+    pub last_error_code: LastErrorCode,
+    /// Type of the last error that occurred in the data phase of a CAN FD
+    /// frame with bit rate switching (DLEC), or [`LastErrorCode::NoChange`]
+    pub data_last_error_code: LastErrorCode,
+    /// A protocol exception event occurred (PXE)
+    pub protocol_exception: bool,
+    /// A CAN FD frame was received (RFDF)
+    pub fd_frame: bool,
+    /// The last received CAN FD frame had its BRS flag set (RBRS)
+    pub bit_rate_switch: bool,
+    /// The last received CAN FD frame had its ESI flag set (RESI)
+    pub error_state_indicator: bool,
+}
+
+impl StickyStatus {
+    const fn new() -> Self {
+        Self {
+            last_error_code: LastErrorCode::NoChange,
+            data_last_error_code: LastErrorCode::NoChange,
+            protocol_exception: false,
+            fd_frame: false,
+            bit_rate_switch: false,
+            error_state_indicator: false,
+        }
+    }
+
+    /// Accumulates the fields of a register value that was just read
+    fn update(&mut self, psr: &PSR) {
+        let last_error_code = psr.lec().variant().into();
+        if last_error_code != LastErrorCode::NoChange {
+            self.last_error_code = last_error_code;
+        }
+        let data_last_error_code = psr.dlec().variant().into();
+        if data_last_error_code != LastErrorCode::NoChange {
+            self.data_last_error_code = data_last_error_code;
+        }
+        self.protocol_exception |= psr.pxe().bit();
+        // RBRS and RESI describe the last received CAN FD frame
+        if psr.rfdf().bit() {
+            self.fd_frame = true;
+            self.bit_rate_switch = psr.rbrs().bit();
+            self.error_state_indicator = psr.resi().bit();
+        }
+    }
+}
+
+/// Type of the last error that occurred on the bus
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LastErrorCode {
+    /// No error occurred since the code was last reset
+    None,
+    /// More than 5 equal bits in a sequence occurred in a part of a received
+    /// frame where this is not allowed
+    Stuff,
+    /// A fixed format part of a received frame has the wrong format
+    Form,
+    /// A transmitted message was not acknowledged by another node
+    Ack,
+    /// A recessive bit was sent, but a dominant level was monitored
+    Bit1,
+    /// A dominant bit was sent, but a recessive level was monitored
+    Bit0,
+    /// The CRC of a received message does not match the computed one
+    Crc,
+    /// No CAN bus event was detected since the code was last read
+    NoChange,
+}
+
+impl From<crate::reg::psr::LECSELECT_A> for LastErrorCode {
+    fn from(value: crate::reg::psr::LECSELECT_A) -> Self {
+        use crate::reg::psr::LECSELECT_A::*;
+        match value {
+            NONE => Self::None,
+            STUFF => Self::Stuff,
+            FORM => Self::Form,
+            ACK => Self::Ack,
+            BIT1 => Self::Bit1,
+            BIT0 => Self::Bit0,
+            CRC => Self::Crc,
+            NC => Self::NoChange,
+        }
+    }
+}
+
+impl From<crate::reg::psr::DLECSELECT_A> for LastErrorCode {
+    fn from(value: crate::reg::psr::DLECSELECT_A) -> Self {
+        use crate::reg::psr::DLECSELECT_A::*;
+        match value {
+            NONE => Self::None,
+            STUFF => Self::Stuff,
+            FORM => Self::Form,
+            ACK => Self::Ack,
+            BIT1 => Self::Bit1,
+            BIT0 => Self::Bit0,
+            CRC => Self::Crc,
+            NC => Self::NoChange,
+        }
+    }
+}
+
+/// CAN FD specific part of the [`ProtocolStatus`]
+#[derive(Copy, Clone, Debug)]
+pub struct FdStatus {
+    /// Type of the last error that occurred in the data phase of a CAN FD
+    /// frame with bit rate switching
+    pub data_last_error_code: LastErrorCode,
+    /// The last received CAN FD frame had its ESI flag set
+    pub error_state_indicator: bool,
+    /// The last received CAN FD frame had its BRS flag set
+    pub bit_rate_switch: bool,
+    /// A CAN FD frame was received since this flag was last reset
+    pub fd_frame: bool,
+}
+
+/// Fault confinement interrupts with a handler for state changes, created by
+/// [`Can::on_fault`]
+///
+/// ```no_run
+/// # use mcan::bus::{DynAux, FaultHandler, FaultState};
+/// // Called from the interrupt handler of the line the interrupts are enabled on
+/// fn on_line<Id: mcan::core::CanId>(
+///     faults: &mut FaultHandler<Id, impl FnMut(FaultState)>,
+///     aux: &impl DynAux<Id = Id>,
+/// ) {
+///     faults.handle_interrupt(aux);
+/// }
+/// ```
+///
+/// # Reentrancy
+///
+/// The handler is called in the interrupt context. It should return quickly
+/// and must not wait for code that the interrupt may have preempted, e.g. by
+/// taking a lock held by the main loop. [`FaultHandler::handle_interrupt`]
+/// takes `&mut self`, so it cannot be reentered; sharing the handler between
+/// interrupts of different priority requires a critical section.
+pub struct FaultHandler<Id, H> {
+    interrupts: OwnedInterruptSet<Id>,
+    handler: H,
+}
+
+impl<Id: mcan_core::CanId, H: FnMut(FaultState)> FaultHandler<Id, H> {
+    /// Clears the fault confinement interrupt flags and, if any was set,
+    /// passes the current fault state to the handler. Returns `true` if the
+    /// handler was called.
+    ///
+    /// Reading the fault state reads the protocol status register, which
+    /// clears some of its fields; see [`DynAux::protocol_status`].
+    pub fn handle_interrupt(&mut self, aux: &impl DynAux<Id = Id>) -> bool {
+        if self.interrupts.take_flags().is_empty() {
+            return false;
+        }
+        (self.handler)(aux.fault_state());
+        true
+    }
+
+    /// Separates the interrupts and the handler again. The interrupts remain
+    /// enabled.
+    pub fn release(self) -> (OwnedInterruptSet<Id>, H) {
+        (self.interrupts, self.handler)
+    }
+}
+
+/// Timestamp wraparound interrupt counting the overflows of the 16-bit
+/// timestamp counter, created by [`Can::track_timestamps`]
+///
+/// Calling [`TimestampTracker::handle_interrupt`] from the interrupt handler
+/// of the line the interrupt is enabled on extends the timestamps of frames
+/// and TX events to 32 bits without polling the counter.
+pub struct TimestampTracker<Id> {
+    interrupts: OwnedInterruptSet<Id>,
+    wraparounds: u16,
+}
+
+impl<Id: mcan_core::CanId> TimestampTracker<Id> {
+    /// Clears the timestamp wraparound flag and counts the wraparound if it
+    /// was set. Returns `true` if a wraparound was counted.
+    pub fn handle_interrupt(&mut self) -> bool {
+        if self.interrupts.take_flags().is_empty() {
+            return false;
+        }
+        self.wraparounds = self.wraparounds.wrapping_add(1);
+        true
+    }
+
+    /// Number of wraparounds counted so far, modulo 2^16
+    pub fn wraparounds(&self) -> u16 {
+        self.wraparounds
+    }
+
+    /// Extends a 16-bit `timestamp` with the number of wraparounds in the
+    /// upper half.
+    ///
+    /// The timestamp must have been captured after the last counted
+    /// wraparound, i.e. frames have to be processed before the counter wraps
+    /// around again.
+    pub fn extend(&self, timestamp: u16) -> u32 {
+        (self.wraparounds as u32) << 16 | timestamp as u32
+    }
+
+    /// Returns the interrupt, which remains enabled.
+    pub fn release(self) -> OwnedInterruptSet<Id> {
+        self.interrupts
+    }
+}
+
 /// Wrapper for the error counters register
 pub struct ErrorCounters(ECR);
 
+impl ErrorCounters {
+    /// Number of protocol errors that caused the transmit or receive error
+    /// counter to be incremented since the register was last read
+    ///
+    /// The peripheral resets this counter whenever the register is read, i.e.
+    /// on every call to [`DynAux::error_counters`]. The value captured here
+    /// is not affected, so adding up the values of subsequent reads yields
+    /// the total number of errors. The counter saturates at 255; a further
+    /// error raises [`Interrupt::ErrorLoggingOverflow`] instead.
+    ///
+    /// [`Interrupt::ErrorLoggingOverflow`]: crate::interrupt::Interrupt::ErrorLoggingOverflow
+    pub fn can_error_logging(&self) -> u8 {
+        self.cel().bits()
+    }
+}
+
 impl Deref for ErrorCounters {
     type Target = ECR;
 
@@ -84,6 +356,128 @@ impl Debug for ErrorCounters {
     }
 }
 
+/// Wrapper for the customer register
+///
+/// Its content is specific to the device integrating the peripheral. On
+/// some devices, e.g. the Microchip SAM E70/S70/V70/V71, it is the Message
+/// RAM configuration register (MRCFG) holding the quality of service of
+/// Message RAM accesses in bits 1:0.
+pub struct MessageRamConfig(CUST);
+
+impl Deref for MessageRamConfig {
+    type Target = CUST;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<CUST> for MessageRamConfig {
+    fn from(value: CUST) -> Self {
+        Self(value)
+    }
+}
+
+impl Debug for MessageRamConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageRamConfig")
+            .field(&format_args!("{:#010x}", self.bits()))
+            .finish()
+    }
+}
+
+/// Sticky status flags that were set before being cleared by
+/// [`Can::clear_status_flags`]
+#[derive(Debug)]
+pub struct StatusFlags {
+    /// Protocol status at the time of clearing. Reading it cleared the error
+    /// codes and the received frame indicators.
+    pub protocol_status: ProtocolStatus,
+    /// Interrupt flags that were cleared
+    pub interrupts: InterruptSet,
+}
+
+/// Mode flags of the CC control register, see [`Can::control_mode`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ControlMode {
+    /// Initialization mode, in which no frames are sent or received (INIT)
+    pub initialization: bool,
+    /// Write access to the protected configuration registers (CCE)
+    pub configuration_change_enabled: bool,
+    /// CAN FD operation (FDOE)
+    pub fd_operation: bool,
+    /// Bit rate switching for transmissions (BRSE)
+    pub bit_rate_switching: bool,
+    /// Bus monitoring mode, in which no frames are sent (MON)
+    pub bus_monitoring: bool,
+    /// Restricted operation mode, in which no frames are sent (ASM)
+    pub restricted_operation: bool,
+    /// Write access to the test register (TEST)
+    pub test_mode: bool,
+    /// Automatic retransmission is disabled (DAR)
+    pub automatic_retransmission_disabled: bool,
+    /// CAN FD frames are sent in the format of Bosch CAN FD 1.0 instead of
+    /// ISO 11898-1:2015 (NISO)
+    pub non_iso: bool,
+    /// A pause of two bit times is inserted after successful transmissions
+    /// (TXP)
+    pub transmit_pause: bool,
+    /// Protocol exception handling is disabled (PXHD)
+    pub protocol_exception_handling_disabled: bool,
+    /// Clock stop was requested (CSR)
+    pub clock_stop_requested: bool,
+    /// Clock stop was acknowledged, so the clocks may be switched off (CSA)
+    pub clock_stop_acknowledged: bool,
+}
+
+impl From<&CCCR> for ControlMode {
+    fn from(cccr: &CCCR) -> Self {
+        Self {
+            initialization: cccr.init().bit_is_set(),
+            configuration_change_enabled: cccr.cce().bit_is_set(),
+            fd_operation: cccr.fdoe().bit_is_set(),
+            bit_rate_switching: cccr.brse().bit_is_set(),
+            bus_monitoring: cccr.mon().bit_is_set(),
+            restricted_operation: cccr.asm().bit_is_set(),
+            test_mode: cccr.test().bit_is_set(),
+            automatic_retransmission_disabled: cccr.dar().bit_is_set(),
+            non_iso: cccr.niso().bit_is_set(),
+            transmit_pause: cccr.txp().bit_is_set(),
+            protocol_exception_handling_disabled: cccr.pxhd().bit_is_set(),
+            clock_stop_requested: cccr.csr().bit_is_set(),
+            clock_stop_acknowledged: cccr.csa().bit_is_set(),
+        }
+    }
+}
+
+/// Fault confinement state of the peripheral
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FaultState {
+    /// Both error counters are below the warning limit of 96
+    ErrorActive,
+    /// At least one error counter has reached the warning limit of 96
+    ErrorWarning,
+    /// At least one error counter has exceeded the error passive limit of 127
+    ErrorPassive,
+    /// The transmit error counter has exceeded 255 and the peripheral does
+    /// not participate in bus activities
+    BusOff,
+}
+
+impl From<&PSR> for FaultState {
+    fn from(psr: &PSR) -> Self {
+        if psr.bo().bit_is_set() {
+            Self::BusOff
+        } else if psr.ep().bit_is_set() {
+            Self::ErrorPassive
+        } else if psr.ew().bit_is_set() {
+            Self::ErrorWarning
+        } else {
+            Self::ErrorActive
+        }
+    }
+}
+
 /// Errors that may occur during configuration
 #[derive(Debug)]
 pub enum ConfigurationError {
@@ -91,11 +485,167 @@ pub enum ConfigurationError {
     BitTiming(BitTimingError),
     /// Time stamp prescaler value is not in the range [1, 16]
     InvalidTimeStampPrescaler,
+    /// The peripheral did not confirm configuration mode (CCE=1) in time, so
+    /// the protected registers could not be written
+    ///
+    /// This typically means that the CAN clock is not running.
+    ConfigurationModeTimeout,
+    /// The [`SharedMemory`] is not located within the 64K of RAM addressable by
+    /// the peripheral
+    MemoryNotAddressable,
+    /// A filter list length exceeds the allocated capacity, see
+    /// [`Can::set_filter_list_len`]
+    FilterListTooLong,
+    /// The endianness test register did not read the expected constant, so
+    /// the register block does not belong to a responsive M_CAN peripheral
+    ///
+    /// This typically means that the base address is wrong or that the
+    /// peripheral is not clocked. Only checked with the `check-peripheral`
+    /// feature enabled.
+    PeripheralNotResponding,
+    /// [`Mode::Fd`] was requested, but support for CAN FD is compiled out by
+    /// the `no-fd-config` feature
+    FdUnsupported,
+    /// The requested change only applies to [`Mode::Fd`], but the peripheral
+    /// operates in [`Mode::Classic`]
+    FdDisabled,
+    /// One of the message types in [`Capacities`] reports a data field size
+    /// code ([`AnyMessage::REG`]) outside of the legal range [0, 7]
+    ///
+    /// [`AnyMessage::REG`]: crate::message::AnyMessage::REG
+    InvalidElementSize,
+    /// The data phase timing is likely to cause errors in combination with
+    /// the nominal timing, see [`check_fd_timing`]
+    ///
+    /// [`check_fd_timing`]: crate::config::check_fd_timing
+    #[cfg(not(feature = "no-fd-config"))]
+    FdTiming(FdTimingIssue),
 }
 
-/// Error that may occur during construction
+/// Errors that may occur when finalizing the configuration
+#[derive(Debug)]
+pub enum FinalizeError {
+    /// The configuration could not be applied
+    Configuration(ConfigurationError),
+    /// The peripheral did not leave initialization mode in time
+    ///
+    /// This typically means that the CAN clock is not running.
+    InitTimeout,
+}
+
+impl From<ConfigurationError> for FinalizeError {
+    fn from(value: ConfigurationError) -> Self {
+        Self::Configuration(value)
+    }
+}
+
+/// The peripheral did not leave initialization mode in time, see
+/// [`Can::resume`]
+///
+/// This typically means that the CAN clock is not running.
 #[derive(Debug)]
-pub struct MemoryNotAddressableError;
+pub struct TimeoutError;
+
+/// Errors that may occur when injecting errors, see
+/// [`CanConfigurable::force_error_injection`]
+#[derive(Debug)]
+pub enum ErrorInjectionError {
+    /// The peripheral did not confirm configuration mode (CCE=1) in time
+    ///
+    /// This typically means that the CAN clock is not running.
+    ConfigurationModeTimeout,
+    /// The peripheral did not join the bus or did not log the errors in time
+    Timeout,
+}
+
+/// Result of [`Can::estimate_actual_bitrate`]
+#[derive(Copy, Clone, Debug)]
+pub struct BitrateEstimate {
+    /// Nominal bitrate derived from the reported CAN clock
+    pub configured: HertzU32,
+    /// Nominal bitrate measured against the reference duration
+    pub measured: HertzU32,
+}
+
+impl BitrateEstimate {
+    /// Returns `true` if the measured bitrate deviates from the configured
+    /// one by more than `tolerance_percent`
+    ///
+    /// The measurement is only as precise as the reference duration and one
+    /// tick of the timestamp counter, so the tolerance has to be chosen
+    /// accordingly.
+    pub fn is_mismatch(&self, tolerance_percent: u32) -> bool {
+        let configured = u64::from(self.configured.to_Hz());
+        let measured = u64::from(self.measured.to_Hz());
+        configured.abs_diff(measured) * 100 > configured * u64::from(tolerance_percent)
+    }
+}
+
+/// Errors that may occur when estimating the actual bitrate, see
+/// [`Can::estimate_actual_bitrate`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BitrateEstimateError {
+    /// The timestamp counter does not count bit times, see
+    /// [`Timestamp::select`]
+    ///
+    /// [`Timestamp::select`]: crate::config::Timestamp::select
+    TimestampNotCounting,
+    /// The reference duration is zero, or too long to be measured by the
+    /// 16-bit timestamp counter
+    InvalidReference,
+}
+
+/// Mode of operation chosen by [`CanConfigurable::configure_with_fallback`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfiguredMode {
+    /// The CAN FD configuration is used
+    Fd,
+    /// The data phase timing of the CAN FD configuration could not be
+    /// derived from the CAN clock, so the classic configuration is used
+    ClassicFallback,
+}
+
+/// Part of the configuration whose register values differ from the expected
+/// ones, see [`CanConfigurable::verify`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigMismatch {
+    /// [`CanConfig::mode`], apart from the data phase timing
+    Mode,
+    /// [`CanConfig::loopback`]
+    Loopback,
+    /// [`CanConfig::nominal_timing`] or [`CanConfig::nominal_timing_raw`]
+    NominalTiming,
+    /// The data phase timing of [`Mode::Fd`]
+    DataPhaseTiming,
+    /// [`CanConfig::timestamp`]
+    Timestamp,
+    /// [`CanConfig::rx_fifo_0`]
+    RxFifo0,
+    /// [`CanConfig::rx_fifo_1`]
+    RxFifo1,
+    /// [`CanConfig::tx`]
+    Tx,
+    /// [`CanConfig::global_filter`]
+    GlobalFilter,
+}
+
+/// Number of register reads after which a transition between initialization,
+/// configuration and normal operation is considered to have failed. The
+/// transition only takes a few CAN clock cycles, so this is very generous.
+const INIT_TIMEOUT_POLLS: u32 = 1_000_000;
+
+/// Bit times [`CanConfigurable::force_error_injection`] waits for the node to
+/// join the bus. This covers the longest frame on a busy bus.
+const INTEGRATION_BIT_TIMES: u32 = 1024;
+
+/// Bit times [`CanConfigurable::force_error_injection`] waits for each error.
+/// On a stuck dominant bus, the receive error counter is raised every 8 bit
+/// times.
+const INJECTION_BIT_TIMES_PER_ERROR: u32 = 32;
+
+/// Constant value of the endianness test register
+#[cfg(feature = "check-peripheral")]
+const ENDIANNESS_TEST_VALUE: u32 = 0x8765_4321;
 
 impl From<BitTimingError> for ConfigurationError {
     fn from(value: BitTimingError) -> Self {
@@ -122,11 +672,30 @@ pub struct Can<'a, Id, D, C: Capacities> {
     /// Message transmission
     pub tx: Tx<'a, Id, C>,
     /// Events for successfully transmitted messages
+    ///
+    /// Disabled and always empty if [`Capacities::TxEventFifo`] is zero.
     pub tx_event_fifo: TxEventFifo<'a, Id>,
     /// Auxiliary bits and bobs
     pub aux: Aux<'a, Id, D>,
+    /// Memory the components above are borrowed from
+    memory: MemoryHandle<'a, C>,
+    /// Reception interrupts masked by [`Self::pause_rx`]
+    rx_paused: Option<InterruptSet>,
 }
 
+/// Exclusive handle to the [`SharedMemory`] of a [`Can`], used to take it
+/// into use again on [`Can::reset`]
+///
+/// All references held by the components of [`Can`] are derived from this
+/// pointer.
+struct MemoryHandle<'a, C: Capacities>(
+    NonNull<SharedMemory<C>>,
+    PhantomData<&'a mut SharedMemory<C>>,
+);
+
+// Safety: The handle represents a `&'a mut SharedMemory<C>`
+unsafe impl<'a, C: Capacities> Send for MemoryHandle<'a, C> where &'a mut SharedMemory<C>: Send {}
+
 /// Auxiliary struct
 ///
 /// Provides unsafe low-level register access as well as other common CAN APIs
@@ -143,6 +712,11 @@ pub struct Aux<'a, Id, D> {
     filters_standard: FiltersStandard<'a, Id>,
     /// Filters for messages with [`embedded_can::ExtendedId`]s
     filters_extended: FiltersExtended<'a, Id>,
+    /// Fault state observed by the last call to
+    /// [`DynAux::error_state_changed`]
+    fault_state: FaultState,
+    /// Fields reset by reads of the protocol status register
+    sticky_status: Cell<StickyStatus>,
 }
 
 /// Trait which erases generic parametrization for [`Aux`] type
@@ -188,22 +762,74 @@ pub trait DynAux {
     fn is_operational(&self) -> bool;
 
     /// Access the error counters register value
+    ///
+    /// Reading the register resets the CAN error logging counter; see
+    /// [`ErrorCounters::can_error_logging`].
     fn error_counters(&self) -> ErrorCounters;
 
     /// Access the protocol status register value
     ///
     /// Reading the register clears fields: PXE, RFDF, RBRS, RESI, DLEC, LEC.
+    /// The cleared fields are retained for [`Self::protocol_status_peek`].
     fn protocol_status(&self) -> ProtocolStatus;
 
+    /// Returns the fields of the protocol status register that are reset when
+    /// it is read, accumulated since they were last taken with
+    /// [`Self::protocol_status_take`], without resetting them.
+    ///
+    /// The register is read through every method of this trait that reads
+    /// it, e.g. [`Self::fault_state`], so it does not matter which one reads
+    /// it first; the fields are retained here either way.
+    fn protocol_status_peek(&self) -> StickyStatus;
+
+    /// Like [`Self::protocol_status_peek`], but resets the accumulated
+    /// fields.
+    fn protocol_status_take(&mut self) -> StickyStatus;
+
     /// Current value of the timestamp counter
     ///
-    /// If timestamping is disabled, its value is zero.
+    /// If timestamping is disabled, its value is zero. With an external
+    /// timestamp source, see [`TimeStampSelect::EXT`], this reads the
+    /// external counter.
+    ///
+    /// [`TimeStampSelect::EXT`]: crate::config::TimeStampSelect::EXT
     fn timestamp(&self) -> u16;
+
+    /// Current fault confinement state
+    ///
+    /// This reads the protocol status register, which clears some of its
+    /// fields; they are retained for [`Self::protocol_status_peek`].
+    fn fault_state(&self) -> FaultState;
+
+    /// Returns the current fault confinement state if it differs from the one
+    /// observed by the previous call. The initial state is assumed to be
+    /// [`FaultState::ErrorActive`].
+    ///
+    /// This reads the protocol status register, which clears some of its
+    /// fields; they are retained for [`Self::protocol_status_peek`].
+    fn error_state_changed(&mut self) -> Option<FaultState>;
+
+    /// Returns `true` if the node is neither receiving nor transmitting and
+    /// is synchronized to the bus.
+    ///
+    /// This reads the protocol status register, which clears some of its
+    /// fields; they are retained for [`Self::protocol_status_peek`].
+    fn is_bus_idle(&self) -> bool;
 }
 
 impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>> Aux<'a, Id, D> {
     fn configuration_mode(&self) {
-        self.reg.configuration_mode()
+        self.reg.configuration_mode(self.config.wait_strategy)
+    }
+
+    /// Reads the protocol status register, retaining the fields that are
+    /// reset by the read
+    fn read_psr(&self) -> PSR {
+        let psr = self.reg.psr.read();
+        let mut sticky = self.sticky_status.get();
+        sticky.update(&psr);
+        self.sticky_status.set(sticky);
+        psr
     }
 }
 
@@ -212,11 +838,11 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>> DynAux for Aux<'a
     type Deps = D;
 
     fn initialization_mode(&self) {
-        self.reg.initialization_mode();
+        self.reg.initialization_mode(self.config.wait_strategy);
     }
 
     fn operational_mode(&self) {
-        self.reg.operational_mode();
+        self.reg.operational_mode(self.config.wait_strategy);
     }
 
     fn is_operational(&self) -> bool {
@@ -236,12 +862,35 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>> DynAux for Aux<'a
     }
 
     fn protocol_status(&self) -> ProtocolStatus {
-        ProtocolStatus(self.reg.psr.read())
+        ProtocolStatus(self.read_psr())
+    }
+
+    fn protocol_status_peek(&self) -> StickyStatus {
+        self.read_psr();
+        self.sticky_status.get()
+    }
+
+    fn protocol_status_take(&mut self) -> StickyStatus {
+        self.read_psr();
+        self.sticky_status.replace(StickyStatus::new())
     }
 
     fn timestamp(&self) -> u16 {
         self.reg.tscv.read().tsc().bits()
     }
+
+    fn fault_state(&self) -> FaultState {
+        FaultState::from(&self.read_psr())
+    }
+
+    fn error_state_changed(&mut self) -> Option<FaultState> {
+        let state = self.fault_state();
+        (core::mem::replace(&mut self.fault_state, state) != state).then_some(state)
+    }
+
+    fn is_bus_idle(&self) -> bool {
+        self.read_psr().act().is_idle()
+    }
 }
 
 /// A CAN bus in configuration mode. Before messages can be sent and received,
@@ -290,42 +939,285 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
         &mut self.0.aux.config
     }
 
-    /// Apply parameters from a bus config struct
-    fn apply_configuration(&mut self) -> Result<(), ConfigurationError> {
+    /// Programs the nominal bit timing register fields directly, accepting
+    /// whatever bitrate results from the CAN clock.
+    ///
+    /// This is an escape hatch for clocks where no exact match for the
+    /// desired bitrate exists, but the deviation is acceptable. Values are
+    /// given in the register representation, i.e. one less than the actual
+    /// value, and are only checked to fit their register fields. The values
+    /// are stored in [`CanConfig::nominal_timing_raw`] and take precedence
+    /// over [`CanConfig::nominal_timing`] until it is reset to `None`.
+    pub fn set_nominal_timing_raw(
+        &mut self,
+        nbrp: u16,
+        ntseg1: u8,
+        ntseg2: u8,
+        nsjw: u8,
+    ) -> Result<(), BitTimingError> {
+        let raw = RawNominalTiming {
+            nbrp,
+            ntseg1,
+            ntseg2,
+            nsjw,
+        };
+        write_nominal_timing_raw(&self.0.aux.reg, &raw)?;
+        self.0.aux.config.nominal_timing_raw = Some(raw);
+        Ok(())
+    }
+
+    /// Reads back the registers and compares them to the values that
+    /// applying `expected` results in. Fails with the first part of the
+    /// configuration that differs.
+    ///
+    /// This is meant for integration tests on hardware, to catch values that
+    /// are silently truncated or encoded incorrectly. A bit timing that
+    /// cannot be applied at all is reported as a mismatch as well.
+    pub fn verify(&self, expected: &CanConfig) -> Result<(), ConfigMismatch> {
+        let reg = &self.0.aux.reg;
+        let can_clock = self.0.aux.dependencies.can_clock();
+        let check = |matches: bool, mismatch| if matches { Ok(()) } else { Err(mismatch) };
+
+        let nominal = match expected.nominal_timing_raw {
+            Some(raw) => raw,
+            None => {
+                let timing = &expected.nominal_timing;
+                let prescaler = timing
+                    .prescaler(can_clock, &NOMINAL_BIT_TIMING_RANGES)
+                    .map_err(|_| ConfigMismatch::NominalTiming)?;
+                RawNominalTiming {
+                    nbrp: prescaler - 1,
+                    ntseg1: timing.phase_seg_1 - 1,
+                    ntseg2: timing.phase_seg_2 - 1,
+                    nsjw: timing.sjw - 1,
+                }
+            }
+        };
+        let nbtp = reg.nbtp.read();
+        check(
+            nbtp.nbrp().bits() == nominal.nbrp
+                && nbtp.ntseg1().bits() == nominal.ntseg1
+                && nbtp.ntseg2().bits() == nominal.ntseg2
+                && nbtp.nsjw().bits() == nominal.nsjw,
+            ConfigMismatch::NominalTiming,
+        )?;
+
+        let cccr = reg.cccr.read();
+        match expected.mode {
+            Mode::Classic => check(
+                cccr.fdoe().bit_is_clear() && cccr.brse().bit_is_clear(),
+                ConfigMismatch::Mode,
+            )?,
+            #[cfg(feature = "no-fd-config")]
+            Mode::Fd { .. } => return Err(ConfigMismatch::Mode),
+            #[cfg(not(feature = "no-fd-config"))]
+            Mode::Fd {
+                allow_bit_rate_switching,
+                data_phase_timing,
+            } => {
+                check(
+                    cccr.fdoe().bit_is_set() && cccr.brse().bit() == allow_bit_rate_switching,
+                    ConfigMismatch::Mode,
+                )?;
+                let prescaler = data_phase_timing
+                    .prescaler(can_clock, &DATA_BIT_TIMING_RANGES)
+                    .map_err(|_| ConfigMismatch::DataPhaseTiming)?;
+                let dbtp = reg.dbtp.read();
+                check(
+                    u16::from(dbtp.dbrp().bits()) == prescaler - 1
+                        && dbtp.dtseg1().bits() == data_phase_timing.phase_seg_1 - 1
+                        && dbtp.dtseg2().bits() == data_phase_timing.phase_seg_2 - 1
+                        && dbtp.dsjw().bits() == data_phase_timing.sjw - 1,
+                    ConfigMismatch::DataPhaseTiming,
+                )?;
+            }
+        }
+
+        check(
+            cccr.test().bit() == expected.loopback
+                && reg.test.read().lbck().bit() == expected.loopback,
+            ConfigMismatch::Loopback,
+        )?;
+
+        let tscc = reg.tscc.read();
+        check(
+            tscc.tss().bits() == u8::from(expected.timestamp.select)
+                && u16::from(tscc.tcp().bits()) + 1 == u16::from(expected.timestamp.prescaler),
+            ConfigMismatch::Timestamp,
+        )?;
+
+        let rxf0c = reg.rxf0.c.read();
+        check(
+            rxf0c.fom().bit() == bool::from(expected.rx_fifo_0.mode)
+                && rxf0c.fwm().bits() == sanitize_watermark(expected.rx_fifo_0.watermark, 64),
+            ConfigMismatch::RxFifo0,
+        )?;
+        let rxf1c = reg.rxf1.c.read();
+        check(
+            rxf1c.fom().bit() == bool::from(expected.rx_fifo_1.mode)
+                && rxf1c.fwm().bits() == sanitize_watermark(expected.rx_fifo_1.watermark, 64),
+            ConfigMismatch::RxFifo1,
+        )?;
+
+        let tx = &expected.tx;
+        check(
+            cccr.dar().bit() == tx.disable_automatic_retransmission
+                && reg.txbc.read().tfqm().bit() == bool::from(tx.tx_queue_submode)
+                && reg.txefc.read().efwm().bits()
+                    == sanitize_watermark(tx.tx_event_fifo_watermark, 32),
+            ConfigMismatch::Tx,
+        )?;
+
+        let gfc = reg.gfc.read();
+        let filter = &expected.global_filter;
+        check(
+            gfc.anfs().bits() == u8::from(ANFSSELECT_A::from(filter.non_matching_standard))
+                && gfc.anfe().bits() == u8::from(ANFESELECT_A::from(filter.non_matching_extended))
+                && gfc.rrfs().bit() == filter.reject_remote_standard
+                && gfc.rrfe().bit() == filter.reject_remote_extended,
+            ConfigMismatch::GlobalFilter,
+        )
+    }
+
+    /// Provokes at least `count` protocol errors by forcing the TX pin
+    /// dominant through the test register, to exercise fault handling such
+    /// as [`DynAux::fault_state`] in tests. Returns the error counters
+    /// afterwards.
+    ///
+    /// The bus is put into operation with test mode enabled and joins the
+    /// bus as usual, which requires 11 consecutive recessive bits. Only then
+    /// is the TX pin forced dominant, until the peripheral has logged `count`
+    /// errors, see [`ErrorCounters::can_error_logging`]. The TX pin, test mode
+    /// and the timestamp counter, which is used to bound the waits in bit
+    /// times, are then restored and the bus is left in configuration mode, so
+    /// the counters carry over to [`Self::finalize`]. A stuck dominant bus
+    /// mainly raises the receive error counter, which can make the node error
+    /// passive; bus off additionally requires transmissions.
+    ///
+    /// This only works if the RX pin follows the TX pin, i.e. with a
+    /// transceiver connected and loopback disabled, as internal loopback
+    /// does not route the TX pin back. It disrupts all traffic on the bus, so
+    /// it must only be used on a dedicated test bus. Fails with
+    /// [`ErrorInjectionError::Timeout`] if the node did not join the bus
+    /// within 1024 bit times, e.g. because the bus is stuck dominant already,
+    /// or if the errors were not logged within 32 bit times each, e.g.
+    /// because the RX pin does not follow the TX pin, or if the timestamp
+    /// counter stalled during the waits, and with
+    /// [`ErrorInjectionError::ConfigurationModeTimeout`] if the peripheral
+    /// did not confirm configuration mode before or after the injection.
+    ///
+    /// ```no_run
+    /// # use mcan::bus::{CanConfigurable, DynAux};
+    /// # use mcan::messageram::Capacities;
+    /// # fn example<Id: mcan::core::CanId, D: mcan::core::Dependencies<Id>, C: Capacities>(
+    /// #     mut can: CanConfigurable<'static, Id, D, C>,
+    /// # ) {
+    /// // Each error raises the receive error counter by 8 at most, so 16
+    /// // errors make the node error passive
+    /// let counters = can.force_error_injection(16).unwrap();
+    /// assert!(counters.rp().bit_is_set());
+    /// let Ok(can) = can.finalize() else { return };
+    /// // Fault handling sees the node error passive after joining the bus
+    /// let fault_state = can.aux.fault_state();
+    /// # let _ = fault_state;
+    /// # }
+    /// ```
+    pub fn force_error_injection(
+        &mut self,
+        count: u8,
+    ) -> Result<ErrorCounters, ErrorInjectionError> {
+        let aux = &self.0.aux;
+        let reg = &aux.reg;
+        let wait = aux.config.wait_strategy;
+        if !reg.try_configuration_mode(INIT_TIMEOUT_POLLS, wait) {
+            return Err(ErrorInjectionError::ConfigurationModeTimeout);
+        }
+        let tscc = reg.tscc.read().bits();
+        // Count bit times to bound the waits independent of the CPU clock
+        reg.tscc.write(|w| w.tss().variant(TimeStampSelect::INC));
+        reg.cccr.modify(|_, w| w.test().set_bit());
+
+        let result = if reg.try_operational_mode(INIT_TIMEOUT_POLLS, wait)
+            && wait_bit_times(aux, INTEGRATION_BIT_TIMES, || {
+                !aux.read_psr().act().is_sync()
+            }) {
+            reg.test.modify(|_, w| w.tx().dominant());
+            // Reset the error logging counter
+            let _ = aux.error_counters();
+            let mut logged = 0u32;
+            let budget = u32::from(count) * INJECTION_BIT_TIMES_PER_ERROR;
+            let injected = wait_bit_times(aux, budget, || {
+                logged += u32::from(aux.error_counters().can_error_logging());
+                logged >= u32::from(count)
+            });
+            reg.test.modify(|_, w| w.tx().core());
+            injected
+        } else {
+            false
+        };
+
+        if !reg.try_configuration_mode(INIT_TIMEOUT_POLLS, wait) {
+            return Err(ErrorInjectionError::ConfigurationModeTimeout);
+        }
+        reg.cccr.modify(|_, w| w.test().bit(aux.config.loopback));
+        // Safety: The previous value is restored.
+        reg.tscc.write(|w| unsafe { w.bits(tscc) });
+        if result {
+            Ok(aux.error_counters())
+        } else {
+            Err(ErrorInjectionError::Timeout)
+        }
+    }
+
+    /// Applies the nominal timing, the mode and the data phase timing from
+    /// [`Self::config`] right away.
+    ///
+    /// [`Self::new`] only sets up the message RAM, and the timing is
+    /// otherwise applied along with all other settings by [`Self::finalize`].
+    /// This allows checking the timing, e.g. with [`Self::verify`], while
+    /// filters and other settings are still being set up. Changing the
+    /// timing afterwards is fine, as [`Self::finalize`] applies it again.
+    pub fn apply_timing(&mut self) -> Result<(), ConfigurationError> {
         let reg = &self.0.aux.reg;
         let config = &self.0.aux.config;
         let dependencies = &self.0.aux.dependencies;
-        if !(1..=16).contains(&config.timestamp.prescaler) {
-            return Err(ConfigurationError::InvalidTimeStampPrescaler);
+        // Writes to protected registers are silently ignored unless CCE=1
+        if !reg.try_configuration_mode(INIT_TIMEOUT_POLLS, config.wait_strategy) {
+            return Err(ConfigurationError::ConfigurationModeTimeout);
         }
 
-        let nominal_prescaler = config
-            .nominal_timing
-            .prescaler(dependencies.can_clock(), &NOMINAL_BIT_TIMING_RANGES)?;
-
-        // Safety: The configuration is checked to be valid when computing the prescaler
-        reg.nbtp.write(|w| unsafe {
-            w.nsjw()
-                .bits(config.nominal_timing.sjw - 1)
-                .ntseg1()
-                .bits(config.nominal_timing.phase_seg_1 - 1)
-                .ntseg2()
-                .bits(config.nominal_timing.phase_seg_2 - 1)
-                .nbrp()
-                .bits(nominal_prescaler - 1)
-        });
-
-        // Safety: Every bit pattern of TCP is valid.
-        reg.tscc.write(|w| unsafe {
-            w.tss()
-                .variant(config.timestamp.select)
-                // Prescaler is 1 + tcp value.
-                .tcp()
-                .bits(config.timestamp.prescaler - 1)
-        });
+        match config.nominal_timing_raw {
+            Some(raw) => write_nominal_timing_raw(reg, &raw)?,
+            None => {
+                let nominal_prescaler = config
+                    .nominal_timing
+                    .prescaler(dependencies.can_clock(), &NOMINAL_BIT_TIMING_RANGES)?;
+
+                // Safety: The configuration is checked to be valid when computing the
+                // prescaler
+                reg.nbtp.write(|w| unsafe {
+                    w.nsjw()
+                        .bits(config.nominal_timing.sjw - 1)
+                        .ntseg1()
+                        .bits(config.nominal_timing.phase_seg_1 - 1)
+                        .ntseg2()
+                        .bits(config.nominal_timing.phase_seg_2 - 1)
+                        .nbrp()
+                        .bits(nominal_prescaler - 1)
+                });
+            }
+        }
 
         match config.mode {
-            Mode::Classic => reg.cccr.modify(|_, w| w.fdoe().clear_bit()),
+            // The data phase timing is meaningless for classic CAN, so DBTP is left
+            // untouched. BRSE is cleared in case FD was enabled by an earlier
+            // configuration.
+            Mode::Classic => reg
+                .cccr
+                .modify(|_, w| w.fdoe().clear_bit().brse().clear_bit()),
+            #[cfg(feature = "no-fd-config")]
+            Mode::Fd { .. } => return Err(ConfigurationError::FdUnsupported),
+            #[cfg(not(feature = "no-fd-config"))]
             Mode::Fd {
                 allow_bit_rate_switching,
                 data_phase_timing,
@@ -334,45 +1226,86 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
                     .modify(|_, w| w.fdoe().set_bit().brse().bit(allow_bit_rate_switching));
                 let data_prescaler = data_phase_timing
                     .prescaler(dependencies.can_clock(), &DATA_BIT_TIMING_RANGES)?;
-                // Safety: The configuration is checked to be valid when computing the prescaler
-                reg.dbtp.write(|w| unsafe {
-                    w.dsjw()
-                        .bits(data_phase_timing.sjw - 1)
-                        .dtseg1()
-                        .bits(data_phase_timing.phase_seg_1 - 1)
-                        .dtseg2()
-                        .bits(data_phase_timing.phase_seg_2 - 1)
-                        .dbrp()
-                        .bits((data_prescaler - 1) as u8)
-                });
+                write_data_phase_timing(reg, &data_phase_timing, data_prescaler);
             }
         };
         // Repopulate mode configuration in `tx`
         self.0.tx.mode = config.mode;
+        Ok(())
+    }
 
-        // Global filter configuration
-        // This setting is redundant and the same behaviour is achievable through main
-        // filter API
-        reg.gfc.write(|w| {
-            w.anfs()
-                .variant(crate::reg::gfc::ANFSSELECT_A::REJECT)
-                .anfe()
-                .variant(crate::reg::gfc::ANFESELECT_A::REJECT)
+    /// Takes over the CAN FD configuration `fd`, or the classic configuration
+    /// `classic` if the data phase timing of `fd` cannot be derived from the
+    /// CAN clock, and applies its timing with [`Self::apply_timing`].
+    /// Returns which of the configurations is used.
+    ///
+    /// This allows deploying the same firmware on buses with and without CAN
+    /// FD support, e.g. when the data bitrate is not achievable with the
+    /// clock of some devices. With the `no-fd-config` feature, `classic` is
+    /// always used. All other errors, e.g. of the nominal timing, are
+    /// returned as is, with the chosen configuration taken over.
+    pub fn configure_with_fallback(
+        &mut self,
+        fd: CanConfig,
+        classic: CanConfig,
+    ) -> Result<ConfiguredMode, ConfigurationError> {
+        let can_clock = self.0.aux.dependencies.can_clock();
+        let fd_usable = match fd.mode {
+            Mode::Classic => false,
+            #[cfg(feature = "no-fd-config")]
+            Mode::Fd { .. } => {
+                let _ = can_clock;
+                false
+            }
+            #[cfg(not(feature = "no-fd-config"))]
+            Mode::Fd {
+                data_phase_timing, ..
+            } => data_phase_timing
+                .prescaler(can_clock, &DATA_BIT_TIMING_RANGES)
+                .is_ok(),
+        };
+        let (config, mode) = if fd_usable {
+            (fd, ConfiguredMode::Fd)
+        } else {
+            (classic, ConfiguredMode::ClassicFallback)
+        };
+        self.0.aux.config = config;
+        self.apply_timing()?;
+        Ok(mode)
+    }
+
+    /// Apply parameters from a bus config struct
+    fn apply_configuration(&mut self) -> Result<(), ConfigurationError> {
+        if !(1..=16).contains(&self.0.aux.config.timestamp.prescaler) {
+            return Err(ConfigurationError::InvalidTimeStampPrescaler);
+        }
+        self.apply_timing()?;
+
+        let reg = &self.0.aux.reg;
+        let config = &self.0.aux.config;
+
+        // Safety: Every bit pattern of TCP is valid.
+        reg.tscc.write(|w| unsafe {
+            w.tss()
+                .variant(config.timestamp.select)
+                // Prescaler is 1 + tcp value.
+                .tcp()
+                .bits(config.timestamp.prescaler - 1)
         });
 
+        // Global filter configuration
+        write_global_filter(reg, &config.global_filter);
+
         // Configure test/loopback mode
         reg.cccr.modify(|_, w| w.test().bit(config.loopback));
+        reg.cccr
+            .modify(|_, w| w.dar().bit(config.tx.disable_automatic_retransmission));
         reg.test.modify(|_, w| w.lbck().bit(config.loopback));
 
         // Configure RX FIFO 0
         reg.rxf0.c.modify(|_, w| {
             let w = w.fom().bit(config.rx_fifo_0.mode.into());
-            let mut watermark = config.rx_fifo_0.watermark;
-            // According to the spec, any value > 64 is interpreted as watermark interrupt
-            // disabled, as is 0.
-            if watermark > 64 {
-                watermark = 0;
-            }
+            let watermark = sanitize_watermark(config.rx_fifo_0.watermark, 64);
             // Safety: The value is sanitized before the write
             unsafe { w.fwm().bits(watermark) }
         });
@@ -380,12 +1313,7 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
         // Configure RX FIFO 1
         reg.rxf1.c.modify(|_, w| {
             let w = w.fom().bit(config.rx_fifo_1.mode.into());
-            let mut watermark = config.rx_fifo_1.watermark;
-            // According to the spec, any value > 64 is interpreted as watermark interrupt
-            // disabled, as is 0.
-            if watermark > 64 {
-                watermark = 0;
-            }
+            let watermark = sanitize_watermark(config.rx_fifo_1.watermark, 64);
             // Safety: The value is sanitized before the write
             unsafe { w.fwm().bits(watermark) }
         });
@@ -396,12 +1324,7 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
 
         // Configure Tx Event Fifo
         reg.txefc.modify(|_, w| {
-            let mut watermark = config.tx.tx_event_fifo_watermark;
-            // According to the spec, any value > 32 is interpreted as watermark interrupt
-            // disabled, as is 0.
-            if watermark > 32 {
-                watermark = 0;
-            }
+            let watermark = sanitize_watermark(config.tx.tx_event_fifo_watermark, 32);
             // Safety: The value is sanitized before the write
             unsafe { w.efwm().bits(watermark) }
         });
@@ -414,6 +1337,11 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
     /// only safeguard keeping the bus operational. Apart from that, the
     /// memory RAM is largely unchecked and an improperly configured linker
     /// script could interfere with bus operations.
+    /// Every section may have a capacity of zero, which writes a length or
+    /// size of zero and disables the section in the peripheral: no filter
+    /// elements are evaluated (frames are then handled by the global filter),
+    /// no frames are stored in the FIFO, or no TX events are recorded. The
+    /// start address of an empty section is not accessed by the peripheral.
     fn apply_ram_config(reg: &crate::reg::Can<Id>, mem: &SharedMemoryInner<C>) {
         // Standard id
         //
@@ -458,6 +1386,9 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
 
         // RX FIFO 0
         //
+        // A FIFO with a capacity of zero elements is written with a size of 0,
+        // which disables it. Its start address is then irrelevant.
+        //
         // Safety:
         // - Pointer is valid assuming SharedMemory location is within first 64K of RAM
         // - Length is checked at compile-time on the `Capacities` constraints level
@@ -470,6 +1401,8 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
 
         // RX FIFO 1
         //
+        // Disabled if the capacity is zero, see RX FIFO 0.
+        //
         // Safety:
         // - Pointer is valid assuming SharedMemory location is within first 64K of RAM
         // - Length is checked at compile-time on the `Capacities` constraints level
@@ -510,36 +1443,69 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
         });
     }
 
+    /// Checks that the data field size codes of all element types fit the
+    /// 3-bit fields of RXESC and TXESC.
+    fn validate_element_sizes() -> Result<(), ConfigurationError> {
+        const MAX_DATA_FIELD_SIZE: u8 = 7;
+        let codes = [
+            C::RxBufferMessage::REG,
+            C::RxFifo0Message::REG,
+            C::RxFifo1Message::REG,
+            C::TxMessage::REG,
+        ];
+        if codes.iter().all(|&code| code <= MAX_DATA_FIELD_SIZE) {
+            Ok(())
+        } else {
+            Err(ConfigurationError::InvalidElementSize)
+        }
+    }
+
     /// Create new can peripheral.
     ///
     /// The hardware requires that SharedMemory is contained within the first
     /// 64K of system RAM. If this condition is not fulfilled, an error is
     /// returned.
     ///
+    /// With the `check-peripheral` feature enabled, the endianness test
+    /// register is verified first, failing with
+    /// [`ConfigurationError::PeripheralNotResponding`] instead of waiting
+    /// forever for a peripheral that does not respond. Without it, an
+    /// unresponsive peripheral is reported as
+    /// [`ConfigurationError::ConfigurationModeTimeout`].
+    ///
     /// The returned peripheral is not operational; use [`Self::finalize`] to
-    /// finish configuration and start transmitting and receiving.
+    /// finish configuration and start transmitting and receiving. Only the
+    /// message RAM layout is applied so far; see [`Self::apply_timing`] for
+    /// applying the bit timing in a separate step.
     pub fn new(
         bitrate: HertzU32,
         dependencies: D,
         memory: &'a mut SharedMemory<C>,
-    ) -> Result<Self, MemoryNotAddressableError> {
+    ) -> Result<Self, ConfigurationError> {
         // Safety:
         // Since `dependencies` field implies ownership of the HW register pointed to by
         // `Id: CanId`, `can` has a unique access to it
         let reg = unsafe { crate::reg::Can::<Id>::new() };
 
-        reg.configuration_mode();
+        #[cfg(feature = "check-peripheral")]
+        if reg.endn.read().etv().bits() != ENDIANNESS_TEST_VALUE {
+            return Err(ConfigurationError::PeripheralNotResponding);
+        }
+
+        // The configuration is yet to be made, so spin as by default
+        if !reg.try_configuration_mode(INIT_TIMEOUT_POLLS, &Spin) {
+            return Err(ConfigurationError::ConfigurationModeTimeout);
+        }
 
         // Contract:
         // `mcan_core::Dependencies::eligible_message_ram_start` contract guarantees
         // `u16::MAX + 1` alignment and points to the beginning of the allocatable CAN
         // memory region.
         if !memory.is_addressable(dependencies.eligible_message_ram_start()) {
-            return Err(MemoryNotAddressableError);
+            return Err(ConfigurationError::MemoryNotAddressable);
         }
 
-        let memory = memory.init();
-        Self::apply_ram_config(&reg, memory);
+        Self::validate_element_sizes()?;
 
         let config = CanConfig::new(bitrate);
 
@@ -551,7 +1517,37 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
 
         let (interrupt_configuration, interrupts) = unsafe { InterruptConfiguration::new() };
 
-        let can = CanConfigurable(Can {
+        // All component references are derived from this handle from now on
+        let memory = MemoryHandle(NonNull::from(memory), PhantomData);
+
+        Ok(Self::from_parts(
+            reg,
+            dependencies,
+            config,
+            interrupt_configuration,
+            interrupts,
+            memory,
+        ))
+    }
+
+    /// Zeroes the message RAM, applies its layout and hands it out to newly
+    /// constructed components.
+    ///
+    /// The peripheral has to be in configuration mode.
+    fn from_parts(
+        reg: crate::reg::Can<Id>,
+        dependencies: D,
+        config: CanConfig,
+        interrupt_configuration: InterruptConfiguration<Id>,
+        interrupts: OwnedInterruptSet<Id, state::Disabled>,
+        handle: MemoryHandle<'a, C>,
+    ) -> Self {
+        // Safety: No references derived from the handle are alive, as `handle` was
+        // either freshly created or moved out of a consumed `Can`.
+        let memory = unsafe { &mut *handle.0.as_ptr() }.init();
+        Self::apply_ram_config(&reg, memory);
+
+        CanConfigurable(Can {
             interrupt_configuration,
             interrupts,
             rx_fifo_0: unsafe { RxFifo::new(&mut memory.rx_fifo_0) },
@@ -569,10 +1565,12 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
                 // disabled.
                 filters_standard: unsafe { FiltersStandard::new(&mut memory.filters_standard) },
                 filters_extended: unsafe { FiltersExtended::new(&mut memory.filters_extended) },
+                fault_state: FaultState::ErrorActive,
+                sticky_status: Cell::new(StickyStatus::new()),
             },
-        });
-
-        Ok(can)
+            memory: handle,
+            rx_paused: None,
+        })
     }
 
     /// Locks the configuration and enters initialization mode.
@@ -586,15 +1584,43 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
     }
 
     /// Locks the configuration and enters normal operation.
-    pub fn finalize(mut self) -> Result<Can<'a, Id, D, C>, ConfigurationError> {
-        self.apply_configuration()?;
-
-        let can = self.0;
+    ///
+    /// If the configuration is invalid or the peripheral does not leave
+    /// initialization mode in time (e.g. because the CAN clock is not
+    /// running), `self` is handed back alongside the error.
+    ///
+    /// On [`FinalizeError::InitTimeout`], configuration mode is re-entered
+    /// with the same bounded poll. Should that fail as well, the returned
+    /// `Self` wraps a peripheral in an unknown mode: writes to protected
+    /// registers may be ignored until the CAN clock recovers and `finalize`
+    /// (or [`release`](Self::release)) is called again.
+    // The error hands back the consumed `CanConfigurable` so the caller can
+    // fix the configuration and retry; `no_std` has no heap to box it on
+    #[allow(clippy::result_large_err)]
+    pub fn finalize(mut self) -> Result<Can<'a, Id, D, C>, (Self, FinalizeError)> {
+        if let Err(e) = self.apply_configuration() {
+            return Err((self, e.into()));
+        }
 
         // Enter normal operation (CCE is set to 0 automatically)
-        can.aux.operational_mode();
+        if !self
+            .0
+            .aux
+            .reg
+            .try_operational_mode(INIT_TIMEOUT_POLLS, self.0.aux.config.wait_strategy)
+        {
+            // Attempt to restore the type invariant of `CanConfigurable`; the
+            // clock that just failed us may keep the peripheral from ever
+            // acknowledging, so do not wait unboundedly
+            let _ = self
+                .0
+                .aux
+                .reg
+                .try_configuration_mode(INIT_TIMEOUT_POLLS, self.0.aux.config.wait_strategy);
+            return Err((self, FinalizeError::InitTimeout));
+        }
 
-        Ok(can)
+        Ok(self.0)
     }
 
     /// Leaves the peripheral non-operational and makes the `Dependencies`
@@ -604,6 +1630,94 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities>
     }
 }
 
+/// According to the spec, any watermark greater than `max` is interpreted as
+/// watermark interrupt disabled, as is 0.
+fn sanitize_watermark(watermark: u8, max: u8) -> u8 {
+    if watermark > max {
+        0
+    } else {
+        watermark
+    }
+}
+
+/// `prescaler` must be computed from `timing` with [`DATA_BIT_TIMING_RANGES`],
+/// which checks the parameters to fit their fields.
+#[cfg(not(feature = "no-fd-config"))]
+fn write_data_phase_timing<Id: mcan_core::CanId>(
+    reg: &crate::reg::Can<Id>,
+    timing: &BitTiming,
+    prescaler: u16,
+) {
+    // Safety: The configuration is checked to be valid when computing the prescaler
+    reg.dbtp.write(|w| unsafe {
+        w.dsjw()
+            .bits(timing.sjw - 1)
+            .dtseg1()
+            .bits(timing.phase_seg_1 - 1)
+            .dtseg2()
+            .bits(timing.phase_seg_2 - 1)
+            .dbrp()
+            .bits((prescaler - 1) as u8)
+    });
+}
+
+fn write_nominal_timing_raw<Id: mcan_core::CanId>(
+    reg: &crate::reg::Can<Id>,
+    raw: &RawNominalTiming,
+) -> Result<(), BitTimingError> {
+    raw.check()?;
+    // Safety: The values are checked to fit their fields
+    reg.nbtp.write(|w| unsafe {
+        w.nsjw()
+            .bits(raw.nsjw)
+            .ntseg1()
+            .bits(raw.ntseg1)
+            .ntseg2()
+            .bits(raw.ntseg2)
+            .nbrp()
+            .bits(raw.nbrp)
+    });
+    Ok(())
+}
+
+/// Polls `done` until it returns `true` or the timestamp counter advanced by
+/// `bit_times`. The counter has to count bit times with a prescaler of one.
+/// Gives up after [`INIT_TIMEOUT_POLLS`] polls as well, as the counter stalls
+/// if the CAN clock stops. Returns the last result of `done`.
+fn wait_bit_times<Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>>(
+    aux: &Aux<'_, Id, D>,
+    bit_times: u32,
+    mut done: impl FnMut() -> bool,
+) -> bool {
+    let mut elapsed = 0u32;
+    let mut last = aux.timestamp();
+    for _ in 0..INIT_TIMEOUT_POLLS {
+        if done() {
+            return true;
+        }
+        let now = aux.timestamp();
+        elapsed += u32::from(now.wrapping_sub(last));
+        last = now;
+        if elapsed > bit_times {
+            return false;
+        }
+    }
+    false
+}
+
+fn write_global_filter<Id: mcan_core::CanId>(reg: &crate::reg::Can<Id>, config: &GlobalFilter) {
+    reg.gfc.write(|w| {
+        w.anfs()
+            .variant(config.non_matching_standard.into())
+            .anfe()
+            .variant(config.non_matching_extended.into())
+            .rrfs()
+            .bit(config.reject_remote_standard)
+            .rrfe()
+            .bit(config.reject_remote_extended)
+    });
+}
+
 impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities> Can<'a, Id, D, C> {
     /// Raw access to the registers.
     ///
@@ -614,6 +1728,491 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities> Ca
         &self.aux.reg
     }
 
+    /// Reads back the bit timing currently configured in the peripheral.
+    ///
+    /// Returns the nominal bit timing and, if CAN FD operation is enabled, the
+    /// data phase bit timing. The bitrates are derived from the CAN clock
+    /// and the configured prescalers.
+    pub fn bit_timing(&self) -> (BitTiming, Option<BitTiming>) {
+        let reg = &self.aux.reg;
+        let can_clock = self.aux.dependencies.can_clock();
+        let nbtp = reg.nbtp.read();
+        let nominal = BitTiming::from_register_fields(
+            can_clock,
+            nbtp.nsjw().bits(),
+            nbtp.ntseg1().bits(),
+            nbtp.ntseg2().bits(),
+            nbtp.nbrp().bits(),
+        );
+        let data = reg.cccr.read().fdoe().bit_is_set().then(|| {
+            let dbtp = reg.dbtp.read();
+            BitTiming::from_register_fields(
+                can_clock,
+                dbtp.dsjw().bits(),
+                dbtp.dtseg1().bits(),
+                dbtp.dtseg2().bits(),
+                dbtp.dbrp().bits().into(),
+            )
+        });
+        (nominal, data)
+    }
+
+    /// Worst case duration of transmitting a data frame with data length code
+    /// `dlc` at the bit timing currently in use, including stuff bits and
+    /// interframe space.
+    ///
+    /// An extended ID is assumed. If `brs` is `true` and CAN FD operation is
+    /// enabled, the data phase is timed at the data bitrate.
+    pub fn frame_transmission_time(&self, dlc: u8, fd: bool, brs: bool) -> MicrosDurationU32 {
+        let (nominal, data) = self.bit_timing();
+        let (arbitration_bits, data_bits) = crate::message::worst_case_frame_bits(dlc, fd);
+        let data_bitrate = match data {
+            Some(data) if brs => data.bitrate,
+            _ => nominal.bitrate,
+        };
+        let nanos = |bits: u32, bitrate: HertzU32| {
+            u64::from(bits) * 1_000_000_000 / u64::from(bitrate.to_Hz().max(1))
+        };
+        let nanos = nanos(arbitration_bits, nominal.bitrate) + nanos(data_bits, data_bitrate);
+        MicrosDurationU32::micros(nanos.div_ceil(1000) as u32)
+    }
+
+    /// Measures the nominal bitrate the peripheral actually runs at, to
+    /// detect a CAN clock that differs from the one reported by the
+    /// `Dependencies`. In that case the configuration succeeds, but the bus
+    /// runs at the wrong bitrate.
+    ///
+    /// The timestamp counter is read before and after `delay`, which has to
+    /// wait for `reference` using a clock that is independent of the CAN
+    /// clock, e.g. a timer of the CPU. As the counter is incremented every
+    /// `prescaler` nominal bit times, the counted bits divided by `reference`
+    /// yield the actual bitrate. No frames are transmitted, so this is
+    /// harmless in loopback mode as well as on a live bus.
+    ///
+    /// Fails if the timestamp counter is not configured with
+    /// [`TimeStampSelect::INC`], or if `reference` is so long that the counter
+    /// could wrap around more than once even at twice the configured bitrate.
+    ///
+    /// [`TimeStampSelect::INC`]: crate::config::TimeStampSelect::INC
+    pub fn estimate_actual_bitrate(
+        &self,
+        reference: MicrosDurationU32,
+        delay: impl FnOnce(MicrosDurationU32),
+    ) -> Result<BitrateEstimate, BitrateEstimateError> {
+        let timestamp = &self.aux.config.timestamp;
+        if timestamp.select != TimeStampSelect::INC {
+            return Err(BitrateEstimateError::TimestampNotCounting);
+        }
+        let configured = self.bit_timing().0.bitrate;
+        let reference_us = u64::from(reference.to_micros());
+        let prescaler = u64::from(timestamp.prescaler);
+        // Allow for an actual bitrate of up to twice the configured one
+        let max_ticks = 2 * reference_us * u64::from(configured.to_Hz()) / prescaler / 1_000_000;
+        if reference_us == 0 || max_ticks > u64::from(u16::MAX) {
+            return Err(BitrateEstimateError::InvalidReference);
+        }
+
+        let start = self.aux.timestamp();
+        delay(reference);
+        let ticks = self.aux.timestamp().wrapping_sub(start);
+        let bits = u64::from(ticks) * prescaler;
+        let measured = HertzU32::Hz((bits * 1_000_000 / reference_us) as u32);
+        Ok(BitrateEstimate {
+            configured,
+            measured,
+        })
+    }
+
+    /// Returns the current fault confinement state if it changed since the
+    /// previous call. See [`DynAux::error_state_changed`].
+    pub fn error_state_changed(&mut self) -> Option<FaultState> {
+        self.aux.error_state_changed()
+    }
+
+    /// Resumes operation after the peripheral entered initialization mode on
+    /// its own, e.g. on a message RAM access failure, keeping the
+    /// configuration. Does nothing if the peripheral is operational.
+    ///
+    /// When the peripheral went Bus-Off, this starts the recovery sequence,
+    /// after which the peripheral takes part in bus activity again.
+    ///
+    /// Unlike [`DynAux::operational_mode`], this gives up if the peripheral
+    /// does not leave initialization mode in time.
+    pub fn resume(&mut self) -> Result<(), TimeoutError> {
+        if self.aux.reg.is_operational()
+            || self
+                .aux
+                .reg
+                .try_operational_mode(INIT_TIMEOUT_POLLS, self.aux.config.wait_strategy)
+        {
+            Ok(())
+        } else {
+            Err(TimeoutError)
+        }
+    }
+
+    /// Enables the bus off, error passive and warning status interrupts on
+    /// `line` and pairs them with a `handler` that is called with the new
+    /// fault state by [`FaultHandler::handle_interrupt`].
+    ///
+    /// Fails if any of these interrupts is no longer owned by
+    /// [`Self::interrupts`].
+    pub fn on_fault<H: FnMut(FaultState)>(
+        &mut self,
+        line: InterruptLine,
+        handler: H,
+    ) -> Result<FaultHandler<Id, H>, MaskError> {
+        let interrupts = self.interrupts.split(
+            [
+                Interrupt::BusOff,
+                Interrupt::ErrorPassive,
+                Interrupt::WarningStatusChanged,
+            ]
+            .into_iter()
+            .collect(),
+        )?;
+        Ok(FaultHandler {
+            interrupts: self.interrupt_configuration.enable(interrupts, line),
+            handler,
+        })
+    }
+
+    /// Enables the timestamp wraparound interrupt on `line` and counts the
+    /// wraparounds with a [`TimestampTracker`].
+    ///
+    /// Fails if the interrupt is no longer owned by [`Self::interrupts`].
+    pub fn track_timestamps(
+        &mut self,
+        line: InterruptLine,
+    ) -> Result<TimestampTracker<Id>, MaskError> {
+        let interrupts = self
+            .interrupts
+            .split([Interrupt::TimestampWraparound].into_iter().collect())?;
+        Ok(TimestampTracker {
+            interrupts: self.interrupt_configuration.enable(interrupts, line),
+            wraparounds: 0,
+        })
+    }
+
+    /// Clears all sticky status flags under the control of `self` and returns
+    /// the ones that were set.
+    ///
+    /// This resets the error codes and received frame indicators of the
+    /// protocol status, including the ones accumulated for
+    /// [`DynAux::protocol_status_peek`], and clears the interrupt flags still
+    /// owned by
+    /// [`Self::interrupts`], e.g. the message lost flags of the RX FIFOs or
+    /// the element lost flag of the TX event FIFO. Interrupts that were split
+    /// off to another [`OwnedInterruptSet`] are not affected and have to be
+    /// cleared through it.
+    pub fn clear_status_flags(&mut self) -> StatusFlags {
+        let protocol_status = self.aux.protocol_status();
+        self.aux.sticky_status.set(StickyStatus::new());
+        StatusFlags {
+            protocol_status,
+            interrupts: self.interrupts.take_flags(),
+        }
+    }
+
+    /// Returns `true` if the peripheral flagged a Message RAM access failure.
+    ///
+    /// Such failures occur when the peripheral cannot access the Message RAM
+    /// in time, e.g. because it is shared with other bus masters, and result
+    /// in frames being dropped. If a transmission was affected, the peripheral
+    /// also switches to restricted operation mode.
+    ///
+    /// This only observes the flag. It is cleared, and can be routed to an
+    /// interrupt line, through the [`OwnedInterruptSet`] that owns
+    /// [`Interrupt::MessageRamAccessFailure`].
+    ///
+    /// [`Interrupt::MessageRamAccessFailure`]: crate::interrupt::Interrupt::MessageRamAccessFailure
+    pub fn ram_access_failed(&self) -> bool {
+        self.aux.reg.ir.read().mraf().bit_is_set()
+    }
+
+    /// Masks the interrupts that signal received messages, so that the
+    /// interrupt handlers stop servicing the RX FIFOs and dedicated RX
+    /// buffers, e.g. during a flash write. Does nothing if reception is
+    /// already paused.
+    ///
+    /// The peripheral keeps receiving and storing messages. Once a FIFO is
+    /// full, new messages are discarded in blocking mode and overwrite the
+    /// oldest ones in overwrite mode, see [`RxFifoMode`]; either way, the
+    /// message lost flag is set. The FIFO capacity and the bus load thus
+    /// bound how long reception can be paused without losing messages.
+    ///
+    /// Only interrupts enabled at the time of the call are masked. The type
+    /// states of their [`OwnedInterruptSet`]s are not changed, and they
+    /// should not be disabled before calling [`Self::resume_rx`], which
+    /// enables them again.
+    ///
+    /// [`RxFifoMode`]: crate::config::RxFifoMode
+    pub fn pause_rx(&mut self) {
+        if self.rx_paused.is_some() {
+            return;
+        }
+        let rx_interrupts = [
+            Interrupt::RxFifo0NewMessage,
+            Interrupt::RxFifo0WatermarkReached,
+            Interrupt::RxFifo0Full,
+            Interrupt::RxFifo0MessageLost,
+            Interrupt::RxFifo1NewMessage,
+            Interrupt::RxFifo1WatermarkReached,
+            Interrupt::RxFifo1Full,
+            Interrupt::RxFifo1MessageLost,
+            Interrupt::HighPriorityMessage,
+            Interrupt::MessageStoredToDedicatedRxBuffer,
+        ];
+        self.rx_paused = Some(
+            self.interrupt_configuration
+                .mask(rx_interrupts.into_iter().collect()),
+        );
+    }
+
+    /// Unmasks the interrupts masked by [`Self::pause_rx`]. Interrupts that
+    /// were flagged in the meantime fire right away, so the handlers catch up
+    /// with the messages received while paused.
+    pub fn resume_rx(&mut self) {
+        if let Some(interrupts) = self.rx_paused.take() {
+            self.interrupt_configuration.unmask(interrupts);
+        }
+    }
+
+    /// Reads back the timestamp counter configuration from the peripheral.
+    ///
+    /// The prescaler is reported as configured, i.e. as the number of bit
+    /// times per tick, although the register holds one less.
+    pub fn timestamp_config(&self) -> Timestamp {
+        let tscc = self.aux.reg.tscc.read();
+        Timestamp {
+            // The reserved value 3 behaves like 0, keeping the counter at zero
+            select: tscc.tss().variant().unwrap_or(TimeStampSelect::ZERO),
+            prescaler: tscc.tcp().bits() + 1,
+        }
+    }
+
+    /// Decodes all mode flags of the CC control register at once, e.g. to
+    /// dump them when the bus behaves unexpectedly.
+    pub fn control_mode(&self) -> ControlMode {
+        ControlMode::from(&self.aux.reg.cccr.read())
+    }
+
+    /// Returns `true` if write access to the protected configuration
+    /// registers is enabled (CCCR.CCE).
+    ///
+    /// This is never the case during normal operation, in which the
+    /// peripheral ignores writes to these registers. Methods changing the
+    /// configuration at runtime enable the access only temporarily.
+    pub fn is_configurable(&self) -> bool {
+        self.aux.reg.cccr.read().cce().bit_is_set()
+    }
+
+    /// Receives all messages from both RX FIFOs, alternating between them so
+    /// that neither FIFO is starved while the other one is busy. Each message
+    /// is passed to `f` together with the FIFO it was taken from. Returns the
+    /// number of messages received.
+    pub fn drain_rx_fair(&mut self, mut f: impl FnMut(RxFrameSource, C::RxFifo0Message)) -> usize
+    where
+        C: Capacities<RxFifo1Message = <C as Capacities>::RxFifo0Message>,
+    {
+        let mut received = 0;
+        loop {
+            let from_fifo_0 = self.rx_fifo_0.next();
+            let from_fifo_1 = self.rx_fifo_1.next();
+            if from_fifo_0.is_none() && from_fifo_1.is_none() {
+                return received;
+            }
+            for (source, message) in [
+                (RxFrameSource::Fifo0, from_fifo_0),
+                (RxFrameSource::Fifo1, from_fifo_1),
+            ] {
+                if let Some(message) = message {
+                    f(source, message);
+                    received += 1;
+                }
+            }
+        }
+    }
+
+    /// Passes every event in the TX event FIFO to `f`, oldest first, and
+    /// returns the number of events.
+    ///
+    /// Each event records a completed transmission of a frame that was
+    /// queued with a message marker. [`TxEvent::event_type`] tells whether
+    /// the frame went out in spite of a cancellation request, e.g. because
+    /// transmission had already started, and [`TxEvent::timestamp`] when
+    /// it went out. Calling this regularly, or on
+    /// [`Interrupt::TxEventFifoNewEntry`], keeps a complete record of
+    /// transmissions as long as the FIFO does not overflow, which is
+    /// reported by [`Interrupt::TxEventFifoElementLost`].
+    ///
+    /// [`Interrupt::TxEventFifoNewEntry`]: crate::interrupt::Interrupt::TxEventFifoNewEntry
+    /// [`Interrupt::TxEventFifoElementLost`]: crate::interrupt::Interrupt::TxEventFifoElementLost
+    pub fn audit_transmissions(&mut self, mut f: impl FnMut(TxEvent)) -> usize {
+        let mut events = 0;
+        while let Some(event) = self.tx_event_fifo.pop() {
+            f(event);
+            events += 1;
+        }
+        events
+    }
+
+    /// Reads the device specific customer register, which configures Message
+    /// RAM accesses on some devices. See [`MessageRamConfig`].
+    pub fn message_ram_config(&self) -> MessageRamConfig {
+        MessageRamConfig(self.aux.reg.cust.read())
+    }
+
+    /// Returns `true` if CAN FD operation is enabled in the peripheral
+    /// (CCCR.FDOE), i.e. [`Mode::Fd`] was applied.
+    pub fn fd_enabled(&self) -> bool {
+        self.aux.reg.cccr.read().fdoe().bit_is_set()
+    }
+
+    /// Returns `true` if bit rate switching is enabled in the peripheral
+    /// (CCCR.BRSE).
+    pub fn brs_enabled(&self) -> bool {
+        self.aux.reg.cccr.read().brse().bit_is_set()
+    }
+
+    /// Changes the handling of frames that do not match any filter.
+    /// `reject_remote` applies to remote frames with both standard and
+    /// extended IDs.
+    ///
+    /// The global filter configuration is write-protected by the peripheral,
+    /// so the bus briefly enters configuration mode and afterwards returns to
+    /// its previous mode of operation. Like [`Self::configure`], this resets
+    /// some status registers, which effectively clears received messages,
+    /// messages pending transmission and transmit events.
+    ///
+    /// Fails with [`ConfigurationError::ConfigurationModeTimeout`] without
+    /// changing the filter if the peripheral does not confirm configuration
+    /// mode in time.
+    pub fn set_global_filter(
+        &mut self,
+        non_matching_standard: NonMatchingAction,
+        non_matching_extended: NonMatchingAction,
+        reject_remote: bool,
+    ) -> Result<(), ConfigurationError> {
+        let config = GlobalFilter {
+            non_matching_standard,
+            non_matching_extended,
+            reject_remote_standard: reject_remote,
+            reject_remote_extended: reject_remote,
+        };
+        self.reconfigure(|reg| write_global_filter(reg, &config))?;
+        self.aux.config.global_filter = config;
+        Ok(())
+    }
+
+    /// Switches between storing all frames that do not match any filter in
+    /// RX FIFO 0, as needed for logging all traffic on the bus, and the
+    /// global filter from the configuration.
+    ///
+    /// Remote frames are only accepted if the configuration does not reject
+    /// them. The configuration itself is left unchanged. Like
+    /// [`Self::set_global_filter`], this briefly enters configuration mode
+    /// and fails if that times out.
+    pub fn set_promiscuous(&mut self, on: bool) -> Result<(), ConfigurationError> {
+        let config = self.aux.config.global_filter;
+        let filter = if on {
+            GlobalFilter {
+                non_matching_standard: NonMatchingAction::StoreFifo0,
+                non_matching_extended: NonMatchingAction::StoreFifo0,
+                ..config
+            }
+        } else {
+            config
+        };
+        self.reconfigure(|reg| write_global_filter(reg, &filter))
+    }
+
+    /// Changes the bitrate of the data phase of bit rate switched CAN FD
+    /// frames, keeping the other data phase timing parameters, e.g. to probe
+    /// which data bitrates the bus tolerates.
+    ///
+    /// This briefly enters configuration mode, which discards pending
+    /// transmissions, and then returns to the previous mode of operation.
+    /// The nominal timing, filters and all other settings are preserved.
+    ///
+    /// Fails with [`ConfigurationError::FdDisabled`] if the peripheral is not
+    /// operating in [`Mode::Fd`], with [`ConfigurationError::FdTiming`] if the
+    /// new data phase timing does not fit the nominal timing, with
+    /// [`ConfigurationError::BitTiming`] if the bitrate cannot be derived from
+    /// the CAN clock, and with [`ConfigurationError::ConfigurationModeTimeout`]
+    /// if the peripheral does not confirm configuration mode in time. Nothing
+    /// is changed in that case. The fit with a nominal timing set by
+    /// [`CanConfigurable::set_nominal_timing_raw`] is not checked.
+    #[cfg(not(feature = "no-fd-config"))]
+    pub fn set_data_bitrate(&mut self, bitrate: HertzU32) -> Result<(), ConfigurationError> {
+        let Mode::Fd {
+            allow_bit_rate_switching,
+            data_phase_timing,
+        } = self.aux.config.mode
+        else {
+            return Err(ConfigurationError::FdDisabled);
+        };
+        let data_phase_timing = BitTiming {
+            bitrate,
+            ..data_phase_timing
+        };
+        if self.aux.config.nominal_timing_raw.is_none() {
+            check_fd_timing(&self.aux.config.nominal_timing, &data_phase_timing)
+                .map_err(ConfigurationError::FdTiming)?;
+        }
+        let can_clock = self.aux.dependencies.can_clock();
+        let prescaler = data_phase_timing.prescaler(can_clock, &DATA_BIT_TIMING_RANGES)?;
+        self.reconfigure(|reg| write_data_phase_timing(reg, &data_phase_timing, prescaler))?;
+        let mode = Mode::Fd {
+            allow_bit_rate_switching,
+            data_phase_timing,
+        };
+        self.aux.config.mode = mode;
+        self.tx.mode = mode;
+        Ok(())
+    }
+
+    /// Sets the number of standard and extended ID filter elements that the
+    /// peripheral considers, which are initially all of the allocated ones.
+    ///
+    /// This allows growing the active filter lists while a large filter table
+    /// is loaded lazily. Shrinking a list disables the filters at its tail
+    /// without modifying them. The lengths are kept when returning to
+    /// configuration mode with [`Self::configure`], but not with
+    /// [`Self::reset`]. Like [`Self::set_global_filter`], this briefly enters
+    /// configuration mode.
+    ///
+    /// Fails without changing either length with
+    /// [`ConfigurationError::FilterListTooLong`] if one of them exceeds the
+    /// capacity of its list, or with
+    /// [`ConfigurationError::ConfigurationModeTimeout`].
+    pub fn set_filter_list_len(
+        &mut self,
+        standard: u8,
+        extended: u8,
+    ) -> Result<(), ConfigurationError> {
+        if usize::from(standard) > self.aux.filters_standard.capacity()
+            || usize::from(extended) > self.aux.filters_extended.capacity()
+        {
+            return Err(ConfigurationError::FilterListTooLong);
+        }
+        self.reconfigure(|reg| {
+            // Safety: The lengths are checked to be within the allocated capacities,
+            // which in turn are checked at compile-time to fit the fields.
+            reg.sidfc.modify(|_, w| unsafe { w.lss().bits(standard) });
+            reg.xidfc.modify(|_, w| unsafe { w.lse().bits(extended) });
+        })
+    }
+
+    /// Runs `f` in configuration mode and returns to the previous mode of
+    /// operation, giving up if configuration mode is not confirmed in time
+    fn reconfigure(&self, f: impl FnOnce(&crate::reg::Can<Id>)) -> Result<(), ConfigurationError> {
+        self.aux
+            .reg
+            .reconfigure(INIT_TIMEOUT_POLLS, self.aux.config.wait_strategy, f)
+            .ok_or(ConfigurationError::ConfigurationModeTimeout)
+    }
+
     /// Return to configuration mode. This resets some status registers, which
     /// effectively clears received messages, messages pending transmission and
     /// tranmit events.
@@ -622,6 +2221,34 @@ impl<'a, Id: mcan_core::CanId, D: mcan_core::Dependencies<Id>, C: Capacities> Ca
         CanConfigurable(self)
     }
 
+    /// Return to configuration mode with the message RAM taken into use
+    /// anew.
+    ///
+    /// Unlike [`Self::configure`], this zeroes the whole message RAM and
+    /// rewrites its layout to the control registers, discarding all received
+    /// and pending messages, transmit events and acceptance filters. The
+    /// configuration as well as the interrupt configuration and interrupts are
+    /// retained.
+    pub fn reset(mut self) -> CanConfigurable<'a, Id, D, C> {
+        self.resume_rx();
+        self.aux.configuration_mode();
+        let Can {
+            interrupt_configuration,
+            interrupts,
+            aux,
+            memory,
+            ..
+        } = self;
+        CanConfigurable::from_parts(
+            aux.reg,
+            aux.dependencies,
+            aux.config,
+            interrupt_configuration,
+            interrupts,
+            memory,
+        )
+    }
+
     /// Disables the peripheral and makes the `Dependencies` available again.
     pub fn release(self) -> D {
         self.configure().release()
@@ -1,5 +1,6 @@
 //! CAN bus configuration
 
+use crate::filter::NonMatchingAction;
 pub use crate::reg::{self, tscc::TSSSELECT_A as TimeStampSelect};
 use core::ops::RangeInclusive;
 use fugit::HertzU32;
@@ -14,6 +15,14 @@ pub struct CanConfig {
     /// Bit timing parameters for everything except the data phase of bit rate
     /// switched FD frames.
     pub nominal_timing: BitTiming,
+    /// Raw nominal bit timing register values. If `Some`, they take precedence
+    /// over `nominal_timing` and no check is made that the resulting bitrate
+    /// matches the requested one.
+    ///
+    /// Usually set through [`CanConfigurable::set_nominal_timing_raw`].
+    ///
+    /// [`CanConfigurable::set_nominal_timing_raw`]: crate::bus::CanConfigurable::set_nominal_timing_raw
+    pub nominal_timing_raw: Option<RawNominalTiming>,
     /// Timestamp configuration
     pub timestamp: Timestamp,
     /// RX Fifo 0
@@ -22,6 +31,127 @@ pub struct CanConfig {
     pub rx_fifo_1: RxFifoConfig,
     /// Tx configuration
     pub tx: TxConfig,
+    /// Handling of frames that are not matched by any filter
+    pub global_filter: GlobalFilter,
+    /// How to wait for the peripheral to acknowledge a change of its mode of
+    /// operation
+    ///
+    /// [`CanConfigurable::new`] always uses [`Spin`], as the configuration
+    /// is not accessible yet.
+    ///
+    /// [`CanConfigurable::new`]: crate::bus::CanConfigurable::new
+    pub wait_strategy: &'static (dyn WaitStrategy + Sync),
+}
+
+/// Waits in between polls of the peripheral during mode changes
+///
+/// Entering and leaving initialization or configuration mode takes a few CAN
+/// clock cycles, which is usually spent spinning. Within an RTOS or an async
+/// executor, a strategy that yields to the scheduler can be used instead, e.g.
+/// a function or closure:
+///
+/// ```no_run
+/// # use mcan::config::CanConfig;
+/// # use fugit::RateExtU32 as _;
+/// fn yield_now() {
+///     // Hand control to the scheduler
+/// }
+///
+/// let mut config = CanConfig::new(500.kHz());
+/// config.wait_strategy = &(yield_now as fn());
+/// ```
+pub trait WaitStrategy {
+    /// Called each time the awaited condition was found to be unmet
+    fn wait(&self);
+}
+
+/// Busy-waits, the default [`WaitStrategy`] suitable for bare-metal use
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Spin;
+
+impl WaitStrategy for Spin {
+    fn wait(&self) {
+        core::hint::spin_loop();
+    }
+}
+
+impl<F: Fn()> WaitStrategy for F {
+    fn wait(&self) {
+        self()
+    }
+}
+
+/// Global filter configuration
+///
+/// Determines what happens to frames that do not match any of the configured
+/// filters, and whether remote frames are filtered at all. Can be assembled
+/// with [`GlobalFilterConfig`].
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GlobalFilter {
+    /// Action for non-matching frames with a standard ID
+    pub non_matching_standard: NonMatchingAction,
+    /// Action for non-matching frames with an extended ID
+    pub non_matching_extended: NonMatchingAction,
+    /// If `true`, all remote frames with a standard ID are rejected.
+    /// Otherwise, they are filtered like data frames.
+    pub reject_remote_standard: bool,
+    /// If `true`, all remote frames with an extended ID are rejected.
+    /// Otherwise, they are filtered like data frames.
+    pub reject_remote_extended: bool,
+}
+
+/// Builder for [`GlobalFilter`]
+///
+/// Starts from the default, which rejects non-matching frames and filters
+/// remote frames like data frames.
+///
+/// ```
+/// # use mcan::config::GlobalFilterConfig;
+/// # use mcan::filter::NonMatchingAction;
+/// let global_filter = GlobalFilterConfig::new()
+///     .standard_nonmatching(NonMatchingAction::StoreFifo0)
+///     .extended_nonmatching(NonMatchingAction::Reject)
+///     .reject_remote_standard(true)
+///     .build();
+/// ```
+#[derive(Default, Copy, Clone, Debug)]
+pub struct GlobalFilterConfig(GlobalFilter);
+
+impl GlobalFilterConfig {
+    /// Creates a builder with the default global filter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the action for non-matching frames with a standard ID
+    pub fn standard_nonmatching(mut self, action: NonMatchingAction) -> Self {
+        self.0.non_matching_standard = action;
+        self
+    }
+
+    /// Sets the action for non-matching frames with an extended ID
+    pub fn extended_nonmatching(mut self, action: NonMatchingAction) -> Self {
+        self.0.non_matching_extended = action;
+        self
+    }
+
+    /// Sets whether all remote frames with a standard ID are rejected
+    pub fn reject_remote_standard(mut self, reject: bool) -> Self {
+        self.0.reject_remote_standard = reject;
+        self
+    }
+
+    /// Sets whether all remote frames with an extended ID are rejected
+    pub fn reject_remote_extended(mut self, reject: bool) -> Self {
+        self.0.reject_remote_extended = reject;
+        self
+    }
+
+    /// Returns the configuration, to be assigned to
+    /// [`CanConfig::global_filter`]
+    pub fn build(self) -> GlobalFilter {
+        self.0
+    }
 }
 
 /// Denotes a TX related configuration
@@ -35,6 +165,10 @@ pub struct TxConfig {
     pub tx_event_fifo_watermark: u8,
     /// TX queue submode
     pub tx_queue_submode: TxQueueMode,
+    /// If `true`, frames that lose arbitration or are disturbed by an error
+    /// are not retransmitted automatically, but reported as cancelled
+    /// instead.
+    pub disable_automatic_retransmission: bool,
 }
 
 /// Bit-timing parameters
@@ -55,7 +189,7 @@ pub struct TxConfig {
 ///
 /// Default time quanta in a bit time is 16 (phase_seg_1 + phase_seg_2 +
 /// synchronization segment (1))
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct BitTiming {
     /// Synchronization jump width
     pub sjw: u8,
@@ -86,10 +220,54 @@ impl BitTiming {
     }
 }
 
+/// Nominal bit timing in the peripheral's representation
+///
+/// All values are written to the register as is, which means they are one
+/// less than the actual value.
+#[derive(Copy, Clone, Debug)]
+pub struct RawNominalTiming {
+    /// Bitrate prescaler, 9 bits
+    pub nbrp: u16,
+    /// Time segment before the sample point, 8 bits
+    pub ntseg1: u8,
+    /// Time segment after the sample point, 7 bits
+    pub ntseg2: u8,
+    /// Synchronization jump width, 7 bits
+    pub nsjw: u8,
+}
+
+impl RawNominalTiming {
+    /// Checks that all values fit their register fields
+    pub(crate) fn check(&self) -> Result<(), BitTimingError> {
+        if self.nbrp > 0x1ff {
+            Err(BitTimingError::PrescalerOutOfRange(0..=0x1ff))
+        } else if self.ntseg2 > 0x7f {
+            Err(BitTimingError::PhaseSeg2OutOfRange(0..=0x7f))
+        } else if self.nsjw > 0x7f {
+            Err(BitTimingError::SynchronizationJumpWidthOutOfRange(0..=0x7f))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Timestamp counter configuration
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Timestamp {
     /// Counting mode of time stamp timer
+    ///
+    /// - [`TimeStampSelect::ZERO`]: timestamps are always zero
+    /// - [`TimeStampSelect::INC`]: the internal counter is incremented every
+    ///   `prescaler` bit times
+    /// - [`TimeStampSelect::EXT`]: the value of a counter external to the
+    ///   peripheral is used, e.g. one that is synchronized to a global clock.
+    ///   Whether and how it is available depends on the device.
+    ///
+    /// The selected source is captured into received messages (see
+    /// [`rx::AnyMessage::timestamp`]) and TX events, so with an external
+    /// source these timestamps are values of the external counter.
+    ///
+    /// [`rx::AnyMessage::timestamp`]: crate::message::rx::AnyMessage::timestamp
     pub select: TimeStampSelect,
     /// Time stamp timer prescaler, bit times per tick
     /// Valid values are: 1 <= ts_prescale <= 16
@@ -130,6 +308,14 @@ pub enum BitTimingError {
         /// Time quanta per bit selected by [`BitTiming`]
         bit_time_quanta: u32,
     },
+    /// No combination of prescaler and segments yields the bitrate, see
+    /// [`BitTiming::from_sample_point`]
+    NoValidTiming {
+        /// Provided peripheral clock
+        can_clock: HertzU32,
+        /// Requested bitrate
+        bitrate: HertzU32,
+    },
 }
 
 /// Valid values of a BitTiming struct
@@ -149,6 +335,7 @@ pub(crate) const NOMINAL_BIT_TIMING_RANGES: BitTimingRanges = BitTimingRanges {
     time_quanta_per_bit: 5..=385,
     prescaler: 1..=512,
 };
+#[cfg(not(feature = "no-fd-config"))]
 pub(crate) const DATA_BIT_TIMING_RANGES: BitTimingRanges = BitTimingRanges {
     sjw: 1..=16,
     phase_seg_1: 1..=32,
@@ -164,6 +351,154 @@ impl BitTiming {
         1 + u32::from(self.phase_seg_1) + u32::from(self.phase_seg_2)
     }
 
+    /// Returns the position of the sample point in percent of the bit time
+    pub fn sample_point(&self) -> f32 {
+        100.0 * (1 + u32::from(self.phase_seg_1)) as f32 / self.time_quanta_per_bit() as f32
+    }
+
+    /// Derives a nominal bit timing for `bitrate` from the CAN clock
+    /// `can_clock`, placing the sample point as close to `sample_point`
+    /// percent of the bit time as possible.
+    ///
+    /// Among the timings closest to the requested sample point, the one with
+    /// the most time quanta per bit is chosen. The synchronization jump width
+    /// is as long as phase segment 2 allows. The achieved sample point is
+    /// reported by [`Self::sample_point`].
+    pub fn from_sample_point(
+        can_clock: HertzU32,
+        bitrate: HertzU32,
+        sample_point: u8,
+    ) -> Result<Self, BitTimingError> {
+        Self::solve(can_clock, bitrate, sample_point, &NOMINAL_BIT_TIMING_RANGES)
+    }
+
+    /// Like [`Self::from_sample_point`], but for the data phase of CAN FD
+    /// frames, whose parameters have narrower ranges.
+    #[cfg(not(feature = "no-fd-config"))]
+    pub fn data_from_sample_point(
+        can_clock: HertzU32,
+        bitrate: HertzU32,
+        sample_point: u8,
+    ) -> Result<Self, BitTimingError> {
+        Self::solve(can_clock, bitrate, sample_point, &DATA_BIT_TIMING_RANGES)
+    }
+
+    /// Enumerates the CAN clock frequencies within `clocks`, in ascending
+    /// order, for which [`Self::from_sample_point`] finds a nominal timing
+    /// that produces `bitrate` exactly.
+    ///
+    /// This is a design-time aid for choosing the peripheral clock. Only
+    /// integer multiples of `bitrate` can be feasible, so the iterator is
+    /// empty if `bitrate` is zero.
+    pub fn feasible_clocks(
+        bitrate: HertzU32,
+        sample_point: u8,
+        clocks: RangeInclusive<HertzU32>,
+    ) -> impl Iterator<Item = HertzU32> {
+        Self::feasible(bitrate, sample_point, clocks, &NOMINAL_BIT_TIMING_RANGES)
+    }
+
+    /// Like [`Self::feasible_clocks`], but for the data phase of CAN FD
+    /// frames, see [`Self::data_from_sample_point`].
+    #[cfg(not(feature = "no-fd-config"))]
+    pub fn data_feasible_clocks(
+        bitrate: HertzU32,
+        sample_point: u8,
+        clocks: RangeInclusive<HertzU32>,
+    ) -> impl Iterator<Item = HertzU32> {
+        Self::feasible(bitrate, sample_point, clocks, &DATA_BIT_TIMING_RANGES)
+    }
+
+    fn feasible(
+        bitrate: HertzU32,
+        sample_point: u8,
+        clocks: RangeInclusive<HertzU32>,
+        valid: &'static BitTimingRanges,
+    ) -> impl Iterator<Item = HertzU32> {
+        let bitrate_hz = bitrate.to_Hz();
+        let (first, last) = match bitrate_hz {
+            0 => (1, 0),
+            _ => (
+                clocks.start().to_Hz().div_ceil(bitrate_hz),
+                clocks.end().to_Hz() / bitrate_hz,
+            ),
+        };
+        (first..=last)
+            .map(move |n| HertzU32::from_raw(n * bitrate_hz))
+            .filter(move |&can_clock| Self::solve(can_clock, bitrate, sample_point, valid).is_ok())
+    }
+
+    fn solve(
+        can_clock: HertzU32,
+        bitrate: HertzU32,
+        sample_point: u8,
+        valid: &BitTimingRanges,
+    ) -> Result<Self, BitTimingError> {
+        let mut best: Option<(f32, Self)> = None;
+        for prescaler in valid.prescaler.clone() {
+            let Some(f_q) = bitrate.to_Hz().checked_mul(prescaler) else {
+                break;
+            };
+            if f_q == 0 || can_clock.to_Hz() % f_q != 0 {
+                continue;
+            }
+            let quanta = can_clock.to_Hz() / f_q;
+            if !valid.time_quanta_per_bit.contains(&quanta) {
+                continue;
+            }
+            // Rounded number of time quanta before the sample point, including
+            // the synchronization segment
+            let before = (quanta * u32::from(sample_point) + 50) / 100;
+            let phase_seg_1 = before
+                .saturating_sub(1)
+                .clamp(*valid.phase_seg_1.start(), *valid.phase_seg_1.end());
+            let Some(phase_seg_2) = (quanta - 1).checked_sub(phase_seg_1) else {
+                continue;
+            };
+            let (Ok(phase_seg_1), Ok(phase_seg_2)) =
+                (u8::try_from(phase_seg_1), u8::try_from(phase_seg_2))
+            else {
+                continue;
+            };
+            let timing = Self {
+                sjw: phase_seg_2.min(u8::try_from(*valid.sjw.end()).unwrap_or(u8::MAX)),
+                phase_seg_1,
+                phase_seg_2,
+                bitrate,
+            };
+            if timing.check(valid).is_err() {
+                continue;
+            }
+            let error = (timing.sample_point() - f32::from(sample_point)).abs();
+            if best.is_none_or(|(best_error, _)| error < best_error) {
+                best = Some((error, timing));
+            }
+        }
+        best.map(|(_, timing)| timing)
+            .ok_or(BitTimingError::NoValidTiming { can_clock, bitrate })
+    }
+
+    /// Decodes the bit timing from register fields. This is the inverse of
+    /// the encoding done when applying the configuration, so all values are
+    /// expected to be one less than the real value.
+    pub(crate) fn from_register_fields(
+        f_can: HertzU32,
+        sjw: u8,
+        phase_seg_1: u8,
+        phase_seg_2: u8,
+        prescaler: u16,
+    ) -> Self {
+        let mut timing = Self {
+            // A nominal phase segment 1 of 256 time quanta is not representable
+            sjw: sjw.saturating_add(1),
+            phase_seg_1: phase_seg_1.saturating_add(1),
+            phase_seg_2: phase_seg_2.saturating_add(1),
+            bitrate: f_can,
+        };
+        timing.bitrate = f_can / ((u32::from(prescaler) + 1) * timing.time_quanta_per_bit());
+        timing
+    }
+
     fn check(&self, valid: &BitTimingRanges) -> Result<(), BitTimingError> {
         if !valid.sjw.contains(&self.sjw.into()) {
             Err(BitTimingError::SynchronizationJumpWidthOutOfRange(
@@ -215,6 +550,61 @@ impl BitTiming {
     }
 }
 
+/// Highest ratio of data to nominal bitrate accepted by [`check_fd_timing`]
+#[cfg(not(feature = "no-fd-config"))]
+pub const MAX_DATA_TO_NOMINAL_RATIO: u32 = 10;
+
+/// Combination of nominal and data phase timing that is valid for the
+/// peripheral, but likely to cause errors on a CAN FD bus, see
+/// [`check_fd_timing`]
+#[cfg(not(feature = "no-fd-config"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FdTimingIssue {
+    /// The data bitrate is lower than the nominal bitrate
+    DataSlowerThanNominal,
+    /// The data bitrate exceeds [`MAX_DATA_TO_NOMINAL_RATIO`] times the
+    /// nominal bitrate, which few transceivers and topologies support
+    ExcessiveRatio,
+    /// The time quantum of the data phase is longer than the one of the
+    /// nominal phase. Using the same prescaler for both phases is
+    /// recommended.
+    DataPrescalerLarger,
+    /// The sample point of the data phase is later than the one of the
+    /// nominal phase, which leaves less margin for the transceiver's
+    /// asymmetric delays where the bits are shortest
+    LateDataSamplePoint,
+}
+
+/// Checks that the `data` phase timing fits the `nominal` one for reliable
+/// CAN FD operation.
+///
+/// The peripheral accepts any combination of timings that can be derived
+/// from its clock, so this catches common misconfigurations that only show
+/// up as errors on the bus.
+#[cfg(not(feature = "no-fd-config"))]
+pub fn check_fd_timing(nominal: &BitTiming, data: &BitTiming) -> Result<(), FdTimingIssue> {
+    let nominal_bitrate = u64::from(nominal.bitrate.to_Hz());
+    let data_bitrate = u64::from(data.bitrate.to_Hz());
+    // Both phases share the CAN clock, so the prescalers compare like the
+    // time quantum frequencies
+    let nominal_quantum = nominal_bitrate * u64::from(nominal.time_quanta_per_bit());
+    let data_quantum = data_bitrate * u64::from(data.time_quanta_per_bit());
+    // Sample points as fractions of the bit time, compared crosswise
+    let nominal_sample = (1 + u32::from(nominal.phase_seg_1)) * data.time_quanta_per_bit();
+    let data_sample = (1 + u32::from(data.phase_seg_1)) * nominal.time_quanta_per_bit();
+    if data_bitrate < nominal_bitrate {
+        Err(FdTimingIssue::DataSlowerThanNominal)
+    } else if data_bitrate > nominal_bitrate * u64::from(MAX_DATA_TO_NOMINAL_RATIO) {
+        Err(FdTimingIssue::ExcessiveRatio)
+    } else if data_quantum < nominal_quantum {
+        Err(FdTimingIssue::DataPrescalerLarger)
+    } else if data_sample > nominal_sample {
+        Err(FdTimingIssue::LateDataSamplePoint)
+    } else {
+        Ok(())
+    }
+}
+
 /// Enable/disable CAN-FD and related features
 #[derive(Default, Copy, Clone)]
 pub enum Mode {
@@ -224,6 +614,8 @@ pub enum Mode {
     Classic,
     /// Transmission and reception of CAN FD frames (with up to 64 bytes of
     /// data) is enabled. This does not prevent use of classic CAN frames.
+    ///
+    /// Applying this mode fails if the `no-fd-config` feature is enabled.
     Fd {
         /// If `true`, FD frames can be transmitted with bit rate switching.
         /// Otherwise, attempts to transmit FD frames will return errors.
@@ -248,10 +640,33 @@ impl CanConfig {
             mode: Default::default(),
             loopback: Default::default(),
             nominal_timing: BitTiming::new(bitrate),
+            nominal_timing_raw: None,
             timestamp: Default::default(),
             rx_fifo_0: Default::default(),
             rx_fifo_1: Default::default(),
             tx: Default::default(),
+            global_filter: Default::default(),
+            wait_strategy: &Spin,
+        }
+    }
+
+    /// Create an instance for a classic CAN bus
+    ///
+    /// CAN FD and test modes are disabled. The nominal bit time consists of 16
+    /// time quanta with the sample point at 87.5%, as commonly recommended
+    /// for classic CAN, so only the prescaler remains to be derived from the
+    /// bitrate. All other settings come pre-populated with default values.
+    pub fn classic(bitrate: HertzU32) -> Self {
+        Self {
+            mode: Mode::Classic,
+            loopback: false,
+            nominal_timing: BitTiming {
+                sjw: 2,
+                phase_seg_1: 13,
+                phase_seg_2: 2,
+                bitrate,
+            },
+            ..Self::new(bitrate)
         }
     }
 }
@@ -304,7 +719,7 @@ impl RxFifoMode {
 }
 
 /// Mode of operation for the RX FIFO (inner enum)
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RxFifoModeVariant {
     /// Blocking mode
     ///
@@ -354,3 +769,136 @@ impl From<TxQueueMode> for bool {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fugit::RateExtU32 as _;
+
+    #[test]
+    fn register_fields_decode_to_encoded_timing() {
+        let f_can = 48.MHz();
+        let timing = BitTiming::new(500.kHz());
+        let prescaler = timing.prescaler(f_can, &NOMINAL_BIT_TIMING_RANGES).unwrap();
+        let decoded = BitTiming::from_register_fields(
+            f_can,
+            timing.sjw - 1,
+            timing.phase_seg_1 - 1,
+            timing.phase_seg_2 - 1,
+            prescaler - 1,
+        );
+        assert_eq!(decoded.sjw, timing.sjw);
+        assert_eq!(decoded.phase_seg_1, timing.phase_seg_1);
+        assert_eq!(decoded.phase_seg_2, timing.phase_seg_2);
+        assert_eq!(decoded.bitrate, timing.bitrate);
+    }
+
+    #[test]
+    fn sample_point_of_default_timing() {
+        assert_eq!(BitTiming::new(500.kHz()).sample_point(), 75.0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "no-fd-config"))]
+    fn fd_timing_issues() {
+        let nominal = BitTiming::new(500.kHz());
+        assert_eq!(check_fd_timing(&nominal, &BitTiming::new(2.MHz())), Ok(()));
+        assert_eq!(
+            check_fd_timing(&nominal, &BitTiming::new(250.kHz())),
+            Err(FdTimingIssue::DataSlowerThanNominal)
+        );
+        assert_eq!(
+            check_fd_timing(&nominal, &BitTiming::new(8.MHz())),
+            Err(FdTimingIssue::ExcessiveRatio)
+        );
+        let coarse = BitTiming {
+            phase_seg_1: 2,
+            phase_seg_2: 1,
+            ..BitTiming::new(500.kHz())
+        };
+        assert_eq!(
+            check_fd_timing(&nominal, &coarse),
+            Err(FdTimingIssue::DataPrescalerLarger)
+        );
+        let late = BitTiming {
+            phase_seg_1: 13,
+            phase_seg_2: 2,
+            ..BitTiming::new(2.MHz())
+        };
+        assert_eq!(
+            check_fd_timing(&nominal, &late),
+            Err(FdTimingIssue::LateDataSamplePoint)
+        );
+    }
+
+    #[test]
+    fn timing_from_sample_point() {
+        let nominal = BitTiming::from_sample_point(48.MHz(), 500.kHz(), 80).unwrap();
+        assert!((nominal.sample_point() - 80.0).abs() < 0.5);
+        let prescaler = nominal
+            .prescaler(48.MHz(), &NOMINAL_BIT_TIMING_RANGES)
+            .unwrap();
+        assert_eq!(
+            48_000_000 / (u32::from(prescaler) * nominal.time_quanta_per_bit()),
+            500_000
+        );
+        assert!(matches!(
+            BitTiming::from_sample_point(48.MHz(), 7.MHz(), 80),
+            Err(BitTimingError::NoValidTiming { .. })
+        ));
+    }
+
+    #[test]
+    fn feasible_clocks_hit_the_bitrate() {
+        let mut clocks = BitTiming::feasible_clocks(500.kHz(), 80, 1.MHz()..=5.MHz());
+        // Phase segment 2 needs at least two time quanta, so the 80% sample
+        // point takes eight time quanta per bit
+        assert_eq!(clocks.next(), Some(4.MHz()));
+        assert_eq!(clocks.next(), Some(4_500.kHz()));
+        for clock in BitTiming::feasible_clocks(1.MHz(), 75, 39.MHz()..=41.MHz()) {
+            assert!(BitTiming::from_sample_point(clock, 1.MHz(), 75).is_ok());
+        }
+        assert_eq!(
+            BitTiming::feasible_clocks(0.Hz(), 80, 1.MHz()..=5.MHz()).count(),
+            0
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "no-fd-config"))]
+    fn data_timing_from_sample_point() {
+        let data = BitTiming::data_from_sample_point(48.MHz(), 2.MHz(), 75).unwrap();
+        assert_eq!(data.sample_point(), 75.0);
+        assert_eq!(data.time_quanta_per_bit(), 24);
+        assert!(data.prescaler(48.MHz(), &DATA_BIT_TIMING_RANGES).is_ok());
+    }
+
+    #[test]
+    fn global_filter_builder() {
+        let filter = GlobalFilterConfig::new()
+            .standard_nonmatching(NonMatchingAction::StoreFifo1)
+            .reject_remote_extended(true)
+            .build();
+        assert_eq!(
+            filter,
+            GlobalFilter {
+                non_matching_standard: NonMatchingAction::StoreFifo1,
+                non_matching_extended: NonMatchingAction::Reject,
+                reject_remote_standard: false,
+                reject_remote_extended: true,
+            }
+        );
+        assert_eq!(GlobalFilterConfig::new().build(), GlobalFilter::default());
+    }
+
+    #[test]
+    fn classic_config() {
+        let config = CanConfig::classic(500.kHz());
+        assert!(matches!(config.mode, Mode::Classic));
+        assert_eq!(config.nominal_timing.sample_point(), 87.5);
+        assert_eq!(
+            config.nominal_timing.time_quanta_per_bit(),
+            BitTiming::new(500.kHz()).time_quanta_per_bit()
+        );
+    }
+}
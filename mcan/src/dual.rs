@@ -0,0 +1,64 @@
+//! Setup of two peripherals at once
+//!
+//! Devices with two M_CAN instances need a [`Dependencies`] and a
+//! [`SharedMemory`] for each of them. [`DualCan`] constructs both and
+//! reports which of them failed, e.g. because its memory is not addressable
+//! from its [`Dependencies::eligible_message_ram_start`].
+//!
+//! The memory regions cannot overlap, as both are taken by exclusive
+//! reference. They may however be eligible for only one of the peripherals
+//! if the peripherals address different parts of the RAM, so each region is
+//! checked against its own peripheral.
+//!
+//! [`Dependencies`]: mcan_core::Dependencies
+//! [`Dependencies::eligible_message_ram_start`]: mcan_core::Dependencies::eligible_message_ram_start
+
+use crate::bus::{CanConfigurable, ConfigurationError};
+use crate::messageram::{Capacities, SharedMemory};
+use fugit::HertzU32;
+
+/// Selects one of the peripherals of a [`DualCan`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BusSlot {
+    /// [`DualCan::can0`]
+    Can0,
+    /// [`DualCan::can1`]
+    Can1,
+}
+
+/// Two peripherals in configuration mode
+pub struct DualCan<'a, Id0, D0, C0: Capacities, Id1, D1, C1: Capacities> {
+    /// First peripheral
+    pub can0: CanConfigurable<'a, Id0, D0, C0>,
+    /// Second peripheral
+    pub can1: CanConfigurable<'a, Id1, D1, C1>,
+}
+
+impl<'a, Id0, D0, C0, Id1, D1, C1> DualCan<'a, Id0, D0, C0, Id1, D1, C1>
+where
+    Id0: mcan_core::CanId,
+    D0: mcan_core::Dependencies<Id0>,
+    C0: Capacities,
+    Id1: mcan_core::CanId,
+    D1: mcan_core::Dependencies<Id1>,
+    C1: Capacities,
+{
+    /// Creates both peripherals with the same `bitrate`, see
+    /// [`CanConfigurable::new`].
+    ///
+    /// Fails with the error of the first peripheral that could not be
+    /// created, identified by its [`BusSlot`].
+    pub fn new(
+        bitrate: HertzU32,
+        dependencies_0: D0,
+        memory_0: &'a mut SharedMemory<C0>,
+        dependencies_1: D1,
+        memory_1: &'a mut SharedMemory<C1>,
+    ) -> Result<Self, (BusSlot, ConfigurationError)> {
+        let can0 = CanConfigurable::new(bitrate, dependencies_0, memory_0)
+            .map_err(|e| (BusSlot::Can0, e))?;
+        let can1 = CanConfigurable::new(bitrate, dependencies_1, memory_1)
+            .map_err(|e| (BusSlot::Can1, e))?;
+        Ok(Self { can0, can1 })
+    }
+}
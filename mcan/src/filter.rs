@@ -41,13 +41,97 @@ impl<'a, P, T: Copy> Filters<'a, P, T> {
             .ok_or(filter)
     }
     /// Appends a `filter` to the back of the list. Returns the assigned index
-    /// if successful. Returns back the `filter` if the list is full.
+    /// if successful. Returns back the `filter` if the list is full, in which
+    /// case no element is modified.
     pub fn push<F: Copy + Into<T>>(&mut self, filter: F) -> Result<usize, F> {
         let index = self.len;
         self.set(index, filter)?;
         self.len += 1;
         Ok(index)
     }
+
+    /// Returns the number of filter elements available
+    pub fn capacity(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Returns the number of elements for which `enabled` holds
+    fn count_enabled(&self, enabled: impl Fn(T) -> bool) -> usize {
+        self.memory.iter().filter(|f| enabled(f.get())).count()
+    }
+
+    /// Decodes the element configuration code extracted by `code` from the
+    /// element at `index`
+    fn decode_action(&self, index: usize, code: impl Fn(T) -> u32) -> Option<FilterAction> {
+        let element = self.memory.get(index)?.get();
+        Some(match code(element) {
+            0 => FilterAction::Disabled,
+            1 => FilterAction::Action(Action::StoreFifo0),
+            2 => FilterAction::Action(Action::StoreFifo1),
+            3 => FilterAction::Action(Action::Reject),
+            4 => FilterAction::Action(Action::Priority),
+            5 => FilterAction::Action(Action::PriorityFifo0),
+            6 => FilterAction::Action(Action::PriorityFifo1),
+            _ => FilterAction::StoreBuffer,
+        })
+    }
+}
+
+impl<'a, P> FiltersStandard<'a, P> {
+    /// Appends a filter that stores all frames with a standard ID in RX FIFO
+    /// 0. Returns the assigned index, or the filter if the list is full.
+    ///
+    /// This makes frames sent in loopback mode show up without setting up
+    /// specific filters. Without any matching filter, frames are handled
+    /// according to [`CanConfig::global_filter`], which rejects them by
+    /// default.
+    ///
+    /// [`CanConfig::global_filter`]: crate::config::CanConfig::global_filter
+    pub fn push_accept_all(&mut self) -> Result<usize, Filter> {
+        self.push(Filter::Classic {
+            action: Action::StoreFifo0,
+            filter: StandardId::ZERO,
+            mask: StandardId::ZERO,
+        })
+    }
+
+    /// Returns the number of elements that are not [`Filter::Disabled`]
+    pub fn enabled(&self) -> usize {
+        // SFEC == 0 disables the element
+        self.count_enabled(|f| f.0 & (0x7 << 27) != 0)
+    }
+
+    /// Returns the action of the filter element at `index`, or `None` if
+    /// `index` is not below [`Self::capacity`]
+    pub fn action_of(&self, index: usize) -> Option<FilterAction> {
+        self.decode_action(index, |f| (f.0 >> 27) & 0x7)
+    }
+}
+
+impl<'a, P> FiltersExtended<'a, P> {
+    /// Appends a filter that stores all frames with an extended ID in RX FIFO
+    /// 0. Returns the assigned index, or the filter if the list is full.
+    ///
+    /// See [`FiltersStandard::push_accept_all`].
+    pub fn push_accept_all(&mut self) -> Result<usize, ExtFilter> {
+        self.push(ExtFilter::Classic {
+            action: Action::StoreFifo0,
+            filter: ExtendedId::ZERO,
+            mask: ExtendedId::ZERO,
+        })
+    }
+
+    /// Returns the number of elements that are not [`ExtFilter::Disabled`]
+    pub fn enabled(&self) -> usize {
+        // EFEC == 0 disables the element
+        self.count_enabled(|f| f.0[0] & (0x7 << 29) != 0)
+    }
+
+    /// Returns the action of the filter element at `index`, or `None` if
+    /// `index` is not below [`Self::capacity`]
+    pub fn action_of(&self, index: usize) -> Option<FilterAction> {
+        self.decode_action(index, |f| f.0[0] >> 29)
+    }
 }
 
 /// 11-bit filter in the peripheral's representation
@@ -171,7 +255,7 @@ pub enum ExtFilter {
 }
 
 /// Filter element configurations
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Action {
     /// Store in RX FIFO 0 if filter matches
     StoreFifo0,
@@ -187,6 +271,51 @@ pub enum Action {
     PriorityFifo1,
 }
 
+/// Configuration of a filter element, see [`FiltersStandard::action_of`] and
+/// [`FiltersExtended::action_of`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FilterAction {
+    /// The element is disabled, see [`Filter::Disabled`]
+    Disabled,
+    /// Matching frames are handled according to the [`Action`]
+    Action(Action),
+    /// Matching frames are stored in a dedicated RX buffer or as debug
+    /// messages, see [`Filter::StoreBuffer`]
+    StoreBuffer,
+}
+
+/// Disposition of frames that do not match any filter
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NonMatchingAction {
+    /// Accept non-matching frames into RX FIFO 0
+    StoreFifo0,
+    /// Accept non-matching frames into RX FIFO 1
+    StoreFifo1,
+    /// Reject non-matching frames
+    #[default]
+    Reject,
+}
+
+impl From<NonMatchingAction> for crate::reg::gfc::ANFSSELECT_A {
+    fn from(val: NonMatchingAction) -> Self {
+        match val {
+            NonMatchingAction::StoreFifo0 => Self::RXF0,
+            NonMatchingAction::StoreFifo1 => Self::RXF1,
+            NonMatchingAction::Reject => Self::REJECT,
+        }
+    }
+}
+
+impl From<NonMatchingAction> for crate::reg::gfc::ANFESELECT_A {
+    fn from(val: NonMatchingAction) -> Self {
+        match val {
+            NonMatchingAction::StoreFifo0 => Self::RXF0,
+            NonMatchingAction::StoreFifo1 => Self::RXF1,
+            NonMatchingAction::Reject => Self::REJECT,
+        }
+    }
+}
+
 impl From<Action> for u32 {
     fn from(val: Action) -> Self {
         match val {
@@ -277,3 +406,103 @@ impl From<ExtFilter> for FilterExtendedId {
         FilterExtendedId([v1, v2])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn capacity_and_enabled_count() {
+        let mut memory: [VolatileCell<FilterStandardId>; 4] =
+            core::array::from_fn(|_| VolatileCell::new(FilterStandardId(0)));
+        let mut filters: FiltersStandard<'_, ()> = unsafe { Filters::new(&mut memory) };
+        assert_eq!(filters.capacity(), 4);
+        assert_eq!(filters.enabled(), 0);
+        let id = StandardId::new(0x10).unwrap();
+        let dual = Filter::Dual {
+            action: Action::StoreFifo0,
+            id1: id,
+            id2: id,
+        };
+        filters.push(dual).ok().unwrap();
+        filters.push(Filter::Disabled).ok().unwrap();
+        filters.push(dual).ok().unwrap();
+        assert_eq!(filters.enabled(), 2);
+    }
+
+    #[test]
+    fn empty_list_rejects_push() {
+        let mut memory: [VolatileCell<FilterExtendedId>; 0] = [];
+        let mut filters: FiltersExtended<'_, ()> = unsafe { Filters::new(&mut memory) };
+        assert_eq!(filters.capacity(), 0);
+        assert!(filters.push_accept_all().is_err());
+        assert_eq!(filters.enabled(), 0);
+    }
+
+    #[test]
+    fn accept_all_matches_any_id() {
+        let mut memory: [VolatileCell<FilterStandardId>; 1] =
+            core::array::from_fn(|_| VolatileCell::new(FilterStandardId(0)));
+        let mut filters: FiltersStandard<'_, ()> = unsafe { Filters::new(&mut memory) };
+        assert_eq!(filters.push_accept_all().ok(), Some(0));
+        assert!(filters.push_accept_all().is_err());
+        // Classic filter storing in FIFO 0, with an all-zero mask
+        assert_eq!(memory[0].get().0, 2 << 30 | 1 << 27);
+    }
+
+    #[test]
+    fn action_of_decodes_single_element() {
+        let mut memory: [VolatileCell<FilterExtendedId>; 4] =
+            core::array::from_fn(|_| VolatileCell::new(FilterExtendedId([0, 0])));
+        let mut filters: FiltersExtended<'_, ()> = unsafe { Filters::new(&mut memory) };
+        let id = ExtendedId::new(0x1234).unwrap();
+        filters.push_accept_all().ok().unwrap();
+        filters
+            .push(ExtFilter::Dual {
+                action: Action::StoreFifo1,
+                id1: id,
+                id2: id,
+            })
+            .ok()
+            .unwrap();
+        filters
+            .push(ExtFilter::StoreBuffer {
+                id,
+                msg_type: SbMsgType::RxBuffer,
+                offset: 0,
+            })
+            .ok()
+            .unwrap();
+        assert_eq!(
+            filters.action_of(0),
+            Some(FilterAction::Action(Action::StoreFifo0))
+        );
+        assert_eq!(
+            filters.action_of(1),
+            Some(FilterAction::Action(Action::StoreFifo1))
+        );
+        assert_eq!(filters.action_of(2), Some(FilterAction::StoreBuffer));
+        assert_eq!(filters.action_of(3), Some(FilterAction::Disabled));
+        assert_eq!(filters.action_of(4), None);
+    }
+
+    #[test]
+    fn push_when_full_keeps_elements() {
+        let mut memory: [VolatileCell<FilterStandardId>; 1] =
+            core::array::from_fn(|_| VolatileCell::new(FilterStandardId(0)));
+        let mut filters: FiltersStandard<'_, ()> = unsafe { Filters::new(&mut memory) };
+        let id = StandardId::new(0x10).unwrap();
+        let dual = |action| Filter::Dual {
+            action,
+            id1: id,
+            id2: id,
+        };
+        assert_eq!(filters.push(dual(Action::StoreFifo0)).ok(), Some(0));
+        assert!(filters.push(dual(Action::Reject)).is_err());
+        assert_eq!(filters.enabled(), 1);
+        assert_eq!(
+            memory[0].get().0,
+            FilterStandardId::from(dual(Action::StoreFifo0)).0
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Forwarding of frames between two buses
+//!
+//! A [`Gateway`] moves frames received in RX FIFO 0 of one bus to the transmit
+//! queue of the other, in both directions. RX FIFO 1 and the dedicated RX
+//! buffers are left alone, so filters can still direct frames meant for the
+//! local node there.
+//!
+//! # Overflow
+//!
+//! Frames are only taken out of an RX FIFO if the destination queue accepts
+//! them. When the destination queue is full, the gateway holds on to at most
+//! one frame per direction and stops draining the source FIFO until the next
+//! [`Gateway::pump`]. Further frames then pile up in the source FIFO, where the
+//! configured [`RxFifoMode`] decides whether new or old frames are lost.
+//!
+//! Frames that the remap closure rejects, or that do not fit the TX elements
+//! of the destination, are dropped and counted in [`PumpResult`].
+//!
+//! [`RxFifoMode`]: crate::config::RxFifoMode
+
+use crate::bus::Can;
+use crate::message::{rx, tx, Raw as _};
+use crate::messageram::Capacities;
+use crate::rx_fifo::DynRxFifo;
+use crate::tx_buffers::DynTx;
+use embedded_can::Id;
+
+/// Direction of a frame passing a [`Gateway`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From bus A to bus B
+    AToB,
+    /// From bus B to bus A
+    BToA,
+}
+
+/// Outcome of a [`Gateway::pump`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PumpResult {
+    /// Frames put in the transmit queue of the other bus
+    pub forwarded: usize,
+    /// Frames rejected by the remap closure
+    pub filtered: usize,
+    /// Frames that could not be transmitted on the other bus, e.g. because
+    /// the payload does not fit its TX elements
+    pub dropped: usize,
+}
+
+/// Forwards frames between two buses, see the [module](self) documentation
+pub struct Gateway<'g, 'a, 'b, IdA, DA, CA, IdB, DB, CB, F>
+where
+    CA: Capacities,
+    CB: Capacities,
+{
+    a: &'g mut Can<'a, IdA, DA, CA>,
+    b: &'g mut Can<'b, IdB, DB, CB>,
+    remap: F,
+    pending_a_to_b: Option<CA::RxFifo0Message>,
+    pending_b_to_a: Option<CB::RxFifo0Message>,
+}
+
+impl<'g, 'a, 'b, IdA, DA, CA, IdB, DB, CB>
+    Gateway<'g, 'a, 'b, IdA, DA, CA, IdB, DB, CB, fn(Direction, Id) -> Option<Id>>
+where
+    IdA: mcan_core::CanId,
+    DA: mcan_core::Dependencies<IdA>,
+    CA: Capacities,
+    IdB: mcan_core::CanId,
+    DB: mcan_core::Dependencies<IdB>,
+    CB: Capacities,
+{
+    /// Create a gateway forwarding all frames unchanged
+    pub fn new(a: &'g mut Can<'a, IdA, DA, CA>, b: &'g mut Can<'b, IdB, DB, CB>) -> Self {
+        Self::with_remap(a, b, |_, id| Some(id))
+    }
+}
+
+impl<'g, 'a, 'b, IdA, DA, CA, IdB, DB, CB, F> Gateway<'g, 'a, 'b, IdA, DA, CA, IdB, DB, CB, F>
+where
+    IdA: mcan_core::CanId,
+    DA: mcan_core::Dependencies<IdA>,
+    CA: Capacities,
+    IdB: mcan_core::CanId,
+    DB: mcan_core::Dependencies<IdB>,
+    CB: Capacities,
+    F: FnMut(Direction, Id) -> Option<Id>,
+{
+    /// Create a gateway that passes the ID of each frame through `remap`.
+    /// Frames for which `remap` returns `None` are not forwarded.
+    pub fn with_remap(
+        a: &'g mut Can<'a, IdA, DA, CA>,
+        b: &'g mut Can<'b, IdB, DB, CB>,
+        remap: F,
+    ) -> Self {
+        Self {
+            a,
+            b,
+            remap,
+            pending_a_to_b: None,
+            pending_b_to_a: None,
+        }
+    }
+
+    /// Forwards frames in both directions until the RX FIFOs are empty or
+    /// the transmit queues are full.
+    pub fn pump(&mut self) -> PumpResult {
+        let mut result = PumpResult::default();
+        forward(
+            &mut self.a.rx_fifo_0,
+            &mut self.b.tx,
+            &mut self.pending_a_to_b,
+            |id| (self.remap)(Direction::AToB, id),
+            &mut result,
+        );
+        forward(
+            &mut self.b.rx_fifo_0,
+            &mut self.a.tx,
+            &mut self.pending_b_to_a,
+            |id| (self.remap)(Direction::BToA, id),
+            &mut result,
+        );
+        result
+    }
+}
+
+fn forward<R, T>(
+    rx: &mut R,
+    tx: &mut T,
+    pending: &mut Option<R::Message>,
+    mut remap: impl FnMut(Id) -> Option<Id>,
+    result: &mut PumpResult,
+) where
+    R: DynRxFifo,
+    R::Message: rx::AnyMessage,
+    T: DynTx,
+    T::Message: tx::AnyMessage,
+{
+    loop {
+        let message = match pending.take() {
+            Some(message) => message,
+            None => match rx.receive() {
+                Ok(message) => message,
+                Err(_) => return,
+            },
+        };
+        let Some(id) = remap(message.id()) else {
+            result.filtered += 1;
+            continue;
+        };
+        let mut builder = rx::AnyMessage::as_tx_builder(&message);
+        builder.id = id;
+        let Ok(frame) = tx::AnyMessage::new(builder) else {
+            result.dropped += 1;
+            continue;
+        };
+        match tx.transmit_queued(frame) {
+            Ok(()) => result.forwarded += 1,
+            Err(nb::Error::WouldBlock) => {
+                *pending = Some(message);
+                return;
+            }
+            Err(nb::Error::Other(_)) => result.dropped += 1,
+        }
+    }
+}
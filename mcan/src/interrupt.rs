@@ -38,7 +38,7 @@
 //! #     type DedicatedTxBuffers = U0;
 //! #     type TxEventFifo = U32;
 //! # }
-//! # let mut can: Can<'static, Can0, (), Caps> = unsafe { std::mem::transmute([0u8; 176]) };
+//! # fn example(mut can: Can<'static, Can0, (), Caps>) {
 //! use mcan::interrupt::{Interrupt, InterruptLine};
 //! // During initialization
 //! let enabled_interrupts = can
@@ -66,6 +66,7 @@
 //!         _ => (),
 //!     }
 //! }
+//! # }
 //! ```
 pub mod state;
 
@@ -78,7 +79,7 @@ use reg::AccessRegisterBlock as _;
 /// The CAN peripheral provides two interrupt lines to the system interrupt
 /// controller. Which interrupts trigger which interrupt line is configurable
 /// via [`InterruptConfiguration`].
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum InterruptLine {
     /// CAN0-line
     Line0,
@@ -298,6 +299,13 @@ pub enum Interrupt {
     /// TCF
     TransmissionCancellationFinished = 10,
     /// TFE
+    ///
+    /// Flagged when the last frame of the transmit queue or FIFO was sent, so
+    /// that all of its buffers are free. This is the cue for burst
+    /// transmitters to refill the queue in one shot, e.g. with
+    /// [`DynTx::transmit_batch`].
+    ///
+    /// [`DynTx::transmit_batch`]: crate::tx_buffers::DynTx::transmit_batch
     TxFifoEmpty = 11,
     /// TEFN
     TxEventFifoNewEntry = 12,
@@ -308,6 +316,10 @@ pub enum Interrupt {
     /// TEFL
     TxEventFifoElementLost = 15,
     /// TSW
+    ///
+    /// Counted by [`TimestampTracker`] to extend timestamps to 32 bits.
+    ///
+    /// [`TimestampTracker`]: crate::bus::TimestampTracker
     TimestampWraparound = 16,
     /// MRAF
     MessageRamAccessFailure = 17,
@@ -502,6 +514,33 @@ impl<Id: mcan_core::CanId, State> OwnedInterruptSet<Id, State> {
         unsafe { Self::new(InterruptSet(split_out)) }
     }
 
+    /// Clears all flagged interrupts owned by this set, regardless of whether
+    /// they are enabled, and returns the ones that were cleared.
+    ///
+    /// Exactly the flags that were read are written back to be cleared, so an
+    /// interrupt flagged in between is not lost and remains pending. This is
+    /// the recommended way to acknowledge interrupts in an interrupt handler.
+    ///
+    /// The flags are owned by the [`OwnedInterruptSet`]s rather than by
+    /// [`InterruptConfiguration`], so this is not available on the latter.
+    pub fn take_flags(&self) -> InterruptSet {
+        // Safety: The mask ensures that only flags under our control are read and
+        // written. Writing a 0 bit leaves the flag unchanged.
+        unsafe {
+            let flagged = self.ir().read().bits() & self.0 .0;
+            self.ir().write(|w| w.bits(flagged));
+            InterruptSet(flagged)
+        }
+    }
+
+    /// # Safety
+    /// This gives access to reads and (through interior mutability) writes of
+    /// IR. The bits not owned by this set must not be affected by these writes
+    /// and must not be relied on by these reads.
+    unsafe fn ir(&self) -> &reg::IR {
+        &(*Id::register_block()).ir
+    }
+
     /// Internal function that allows conversions from any state to any state.
     ///
     /// # Safety
@@ -521,10 +560,10 @@ impl<Id: mcan_core::CanId, State: state::MaybeEnabled> OwnedInterruptSet<Id, Sta
 
     /// Clears the flagged interrupts owned by this `OwnedInterruptSet` and
     /// provides an iterator over the flags that were cleared.
+    ///
+    /// See [`take_flags`](Self::take_flags).
     pub fn iter_flagged(&self) -> Iter {
-        let interrupts = self.interrupt_flags();
-        self.clear_interrupts(interrupts);
-        interrupts.iter()
+        self.take_flags().iter()
     }
 
     /// Get the subset of interrupts in this set that are currently flagged.
@@ -545,16 +584,34 @@ impl<Id: mcan_core::CanId, State: state::MaybeEnabled> OwnedInterruptSet<Id, Sta
             self.ir().write(|w| w.bits(masked));
         }
     }
+}
 
-    /// # Safety
-    /// This gives access to reads and (through interior mutability) writes of
-    /// IR. The bits not owned by this set must not be affected by these writes
-    /// and must not be relied on by these reads.
-    unsafe fn ir(&self) -> &reg::IR {
-        &(*Id::register_block()).ir
+/// Interrupts to enable on each line, see [`InterruptConfiguration::apply`]
+#[derive(Copy, Clone, Debug)]
+pub struct InterruptSetup {
+    /// Interrupts routed to line 0
+    pub line_0: InterruptSet,
+    /// Interrupts routed to line 1
+    pub line_1: InterruptSet,
+}
+
+impl Default for InterruptSetup {
+    fn default() -> Self {
+        Self {
+            line_0: InterruptSet(0),
+            line_1: InterruptSet(0),
+        }
     }
 }
 
+/// Interrupts enabled by [`InterruptConfiguration::apply`]
+pub struct EnabledInterrupts<Id> {
+    /// Interrupts enabled on line 0
+    pub line_0: OwnedInterruptSet<Id, state::EnabledLine0>,
+    /// Interrupts enabled on line 1
+    pub line_1: OwnedInterruptSet<Id, state::EnabledLine1>,
+}
+
 /// Controls enabling and line selection of interrupts.
 pub struct InterruptConfiguration<P>(PhantomData<P>);
 
@@ -607,6 +664,88 @@ impl<Id: mcan_core::CanId> InterruptConfiguration<Id> {
         unsafe { interrupt.convert() }
     }
 
+    /// Enables all interrupts of `setup` at once, taking them from the
+    /// `disabled` ones.
+    ///
+    /// Line selection, interrupt enables and the enables of the lines that
+    /// interrupts are routed to are each written once, so no interrupt fires
+    /// on the wrong line in between. Interrupts not in `setup` are not
+    /// affected.
+    ///
+    /// Fails without changing anything if an interrupt is not owned by
+    /// `disabled`, or if it is selected for both lines.
+    pub fn apply(
+        &mut self,
+        disabled: &mut OwnedInterruptSet<Id, state::Disabled>,
+        setup: InterruptSetup,
+    ) -> Result<EnabledInterrupts<Id>, MaskError> {
+        let (line_0, line_1) = (setup.line_0.0, setup.line_1.0);
+        if line_0 & line_1 != 0 {
+            return Err(MaskError(InterruptSet(line_0 & line_1)));
+        }
+        let mut both = disabled.split(InterruptSet(line_0 | line_1))?;
+        let on_line_1 = both.split_leniently(setup.line_1);
+
+        // Safety: The reserved bits are 0 by type invariant on `OwnedInterruptSet`.
+        self.ils()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !line_0 | line_1) });
+        self.ie()
+            .modify(|r, w| unsafe { w.bits(r.bits() | line_0 | line_1) });
+        self.ile().modify(|r, w| {
+            w.eint0()
+                .bit(r.eint0().bit() || line_0 != 0)
+                .eint1()
+                .bit(r.eint1().bit() || line_1 != 0)
+        });
+        // Safety: The interrupts were enabled on the lines matching the type state
+        unsafe {
+            Ok(EnabledInterrupts {
+                line_0: both.convert(),
+                line_1: on_line_1.convert(),
+            })
+        }
+    }
+
+    /// Returns the line that `interrupt` is routed to (ILS), regardless of
+    /// whether it is enabled.
+    pub fn line_of(&self, interrupt: Interrupt) -> InterruptLine {
+        if self.ils().read().bits() & u32::from(interrupt) != 0 {
+            InterruptLine::Line1
+        } else {
+            InterruptLine::Line0
+        }
+    }
+
+    /// Disables the interrupts of `interrupts` that are enabled and returns
+    /// them, so that they can be restored with [`Self::unmask`].
+    ///
+    /// Like [`Self::set_lines_enabled`], this does not change the type state
+    /// of the [`OwnedInterruptSet`]s the interrupts belong to.
+    pub(crate) fn mask(&mut self, interrupts: InterruptSet) -> InterruptSet {
+        let masked = self.ie().read().bits() & interrupts.0;
+        // Safety: Only bits that are set in IE are cleared.
+        self.ie()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !masked) });
+        InterruptSet(masked)
+    }
+
+    /// Enables interrupts that were disabled by [`Self::mask`]
+    pub(crate) fn unmask(&mut self, interrupts: InterruptSet) {
+        // Safety: Only bits that were cleared by `mask` are set.
+        self.ie()
+            .modify(|r, w| unsafe { w.bits(r.bits() | interrupts.0) });
+    }
+
+    /// Enables or disables both interrupt lines at once, without affecting
+    /// which interrupts are enabled and their line selection.
+    ///
+    /// This is a cheap way to mask all interrupts of the peripheral, e.g.
+    /// during a short critical section. Note that enabling interrupts on a
+    /// line also enables the line itself.
+    pub fn set_lines_enabled(&mut self, on: bool) {
+        self.ile().write(|w| w.eint0().bit(on).eint1().bit(on));
+    }
+
     /// # Safety
     /// Caller must make sure that the type state matches the selected `line`.
     unsafe fn raw_enable<In, Out: state::MaybeEnabled>(
@@ -718,6 +857,17 @@ mod test {
         assert_eq!(iter_collect(0x2aaa_aaaa), 0x2aaa_aaaa);
     }
 
+    #[test]
+    fn tx_fifo_empty_is_tfe() {
+        let set = InterruptSet::from(Interrupt::TxFifoEmpty);
+        assert!(set.tfe());
+        assert_eq!(set.0, 1 << 11);
+        assert!(matches!(
+            Interrupt::try_from(11),
+            Ok(Interrupt::TxFifoEmpty)
+        ));
+    }
+
     #[test]
     fn iter_collect_drops_reserved_bits() {
         assert_eq!(iter_collect(0xffff_ffff), 0x3fff_ffff);
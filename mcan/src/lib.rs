@@ -242,7 +242,9 @@
 //!     .unwrap_or_else(|_| panic!("Extended filter application failed"));
 //!
 //! // Call to `finalize` puts MCAN into operational mode
-//! let can = can.finalize().unwrap();
+//! let can = can
+//!     .finalize()
+//!     .unwrap_or_else(|_| panic!("Finalizing the configuration failed"));
 //!
 //! // `can` object can be split into independent pieces
 //! let rx_fifo_0 = can.rx_fifo_0;
@@ -263,7 +265,10 @@
 
 pub mod bus;
 pub mod config;
+pub mod dual;
 pub mod filter;
+#[cfg(feature = "gateway")]
+pub mod gateway;
 pub mod interrupt;
 pub mod message;
 pub mod messageram;
@@ -271,6 +276,9 @@ pub mod prelude;
 pub mod reg;
 pub mod rx_dedicated_buffers;
 pub mod rx_fifo;
+#[cfg(feature = "rx-ring")]
+pub mod rx_ring;
+pub mod timestamp;
 pub mod tx_buffers;
 pub mod tx_event_fifo;
 
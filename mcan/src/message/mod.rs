@@ -37,6 +37,65 @@ impl_any_message!(64, 7);
 #[derive(Debug)]
 pub struct TooMuchData;
 
+/// Inconsistent length or format of a received frame, reported by
+/// [`rx::AnyMessage::validate_length`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LengthError {
+    /// A CAN FD frame is marked as remote frame, which does not exist in
+    /// CAN FD
+    RemoteFd,
+    /// A classic frame is marked as bit rate switched, which only exists in
+    /// CAN FD
+    BitRateSwitchWithoutFd,
+    /// The payload is longer than the element, so part of it was discarded
+    Truncated {
+        /// Length in bytes encoded by the data length code
+        len: usize,
+        /// Data bytes the element holds
+        capacity: usize,
+    },
+}
+
+/// Frame formats to keep on the receive path, see [`Self::accepts`] and
+/// [`RxFifo::receive_format`]
+///
+/// The peripheral cannot drop frames based on their format: neither the
+/// filter elements nor [`CanConfig::global_filter`] match the FDF bit, and
+/// with CAN FD operation enabled, both classic and CAN FD frames are
+/// received. Frames of the unwanted format therefore occupy RX FIFO elements
+/// and have to be discarded in software on receive, where they can also be
+/// counted, e.g. as a fault indication on a CAN FD only segment.
+///
+/// With CAN FD operation disabled, see [`Mode::Classic`], the peripheral
+/// does not receive CAN FD frames at all, but signals protocol errors for
+/// them instead.
+///
+/// [`RxFifo::receive_format`]: crate::rx_fifo::RxFifo::receive_format
+/// [`CanConfig::global_filter`]: crate::config::CanConfig::global_filter
+/// [`Mode::Classic`]: crate::config::Mode::Classic
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameFormatFilter {
+    /// Keep classic and CAN FD frames
+    #[default]
+    Any,
+    /// Keep classic frames only
+    ClassicOnly,
+    /// Keep CAN FD frames only
+    FdOnly,
+}
+
+impl FrameFormatFilter {
+    /// Returns `true` if frames in the format of `message` are kept, based
+    /// on [`Raw::fd_format`]
+    pub fn accepts(&self, message: &impl Raw) -> bool {
+        match self {
+            Self::Any => true,
+            Self::ClassicOnly => !message.fd_format(),
+            Self::FdOnly => message.fd_format(),
+        }
+    }
+}
+
 /// CAN frame/message.
 pub enum Message<const N: usize> {
     /// Message received from a CAN bus
@@ -115,15 +174,27 @@ struct RawMessage<const N: usize> {
 pub trait Raw {
     /// Returns the CAN identifier of the message
     fn id(&self) -> Id;
-    /// Data length in bytes
+    /// Data length in bytes, decoded from [`Self::dlc`]
+    ///
+    /// In classic frames, data length codes 9 to 15 denote 8 bytes. In CAN FD
+    /// frames, they denote 12 to 64 bytes.
     fn decoded_dlc(&self) -> usize;
-    /// Data length code
+    /// Data length code, the raw 4-bit value as sent on the bus
     fn dlc(&self) -> u8;
     /// True if the header indicates that the frame uses the CAN FD format
+    ///
+    /// This is the FDF bit of the element and tells classic frames apart
+    /// from CAN FD frames, regardless of whether the latter use bit rate
+    /// switching, see [`Self::bit_rate_switching`].
     fn fd_format(&self) -> bool;
     /// Remote Transmission Request
     fn is_remote_frame(&self) -> bool;
-    /// Data field
+    /// Data field, the valid bytes borrowed from the element
+    ///
+    /// The length is the one encoded by the data length code, see
+    /// [`Self::decoded_dlc`], clamped to the element size, so padding after
+    /// the payload and bytes beyond the element are never included. Remote
+    /// frames have no data field.
     fn data(&self) -> &[u8];
     /// Check if the frame uses and extended (29-bit) ID
     fn is_extended(&self) -> bool;
@@ -188,6 +259,34 @@ impl<const N: usize> Raw for RawMessage<N> {
     }
 }
 
+/// Compares the parts of two frames that are transmitted on the bus: ID, DLC,
+/// payload and the RTR/FDF/BRS/ESI flags. Padding beyond the DLC and
+/// reception or transmission metadata are ignored.
+fn frame_eq(a: &impl Raw, b: &impl Raw) -> bool {
+    a.id() == b.id()
+        && a.is_remote_frame() == b.is_remote_frame()
+        && a.dlc() == b.dlc()
+        && a.fd_format() == b.fd_format()
+        && a.bit_rate_switching() == b.bit_rate_switching()
+        && a.is_transmitter_error_passive() == b.is_transmitter_error_passive()
+        && a.data() == b.data()
+}
+
+macro_rules! impl_frame_eq {
+    ($lhs:ty, $rhs:ty) => {
+        impl<const N: usize, const M: usize> PartialEq<$rhs> for $lhs {
+            fn eq(&self, other: &$rhs) -> bool {
+                frame_eq(self, other)
+            }
+        }
+    };
+}
+
+impl_frame_eq!(rx::Message<N>, rx::Message<M>);
+impl_frame_eq!(tx::Message<N>, tx::Message<M>);
+impl_frame_eq!(rx::Message<N>, tx::Message<M>);
+impl_frame_eq!(tx::Message<N>, rx::Message<M>);
+
 /// Finds the smallest data length code that encodes at least len bytes
 fn len_to_dlc(len: usize, fd_format: bool) -> Result<u8, TooMuchData> {
     if fd_format {
@@ -230,3 +329,376 @@ fn dlc_to_len(dlc: u8, fd_format: bool) -> usize {
         }
     }
 }
+
+/// Worst case number of bits on the wire for a data frame with an extended
+/// ID, including stuff bits and interframe space. Returns the bits sent at the
+/// nominal bitrate and the bits of the data phase, which are sent at the data
+/// bitrate if bit rate switching is used.
+pub(crate) fn worst_case_frame_bits(dlc: u8, fd_format: bool) -> (u32, u32) {
+    let data_bits = 8 * dlc_to_len(dlc, fd_format) as u32;
+    // Stuff bits are inserted after every 4 bits in the worst case
+    let stuffed = |bits: u32| bits + (bits - 1) / 4;
+    // CRC delimiter, ACK slot and delimiter, EOF and interframe space
+    let trailer = 1 + 2 + 7 + 3;
+    if fd_format {
+        // SOF, base ID, SRR, IDE, ID extension, RRS, FDF, res, BRS
+        let arbitration = 1 + 11 + 1 + 1 + 18 + 1 + 1 + 1 + 1;
+        // ESI and DLC followed by the data field
+        let control_and_data = 1 + 4 + data_bits;
+        let crc = if data_bits <= 16 * 8 { 17 } else { 21 };
+        // Stuff count and CRC with a fixed stuff bit every 4 bits
+        let crc_field: u32 = 4 + crc;
+        let crc_field = crc_field + crc_field.div_ceil(4);
+        (
+            stuffed(arbitration) + trailer,
+            control_and_data + control_and_data / 4 + crc_field,
+        )
+    } else {
+        // SOF, base ID, SRR, IDE, ID extension, RTR, r1, r0, DLC, data, CRC
+        let stuffed_bits = 1 + 11 + 1 + 1 + 18 + 1 + 2 + 4 + data_bits + 15;
+        (stuffed(stuffed_bits) + trailer, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn roundtrip(id: Id) -> Id {
+        tx::MessageBuilder {
+            id,
+            frame_type: tx::FrameType::Classic(tx::ClassicFrameType::Data(&[])),
+            store_tx_event: None,
+        }
+        .build::<8>()
+        .unwrap()
+        .id()
+    }
+
+    fn classic(id: u16, data: &[u8]) -> tx::MessageBuilder<'_> {
+        tx::MessageBuilder {
+            id: StandardId::new(id).unwrap().into(),
+            frame_type: tx::FrameType::Classic(tx::ClassicFrameType::Data(data)),
+            store_tx_event: None,
+        }
+    }
+
+    #[test]
+    fn frame_eq_ignores_padding_and_metadata() {
+        let sent = classic(0x123, &[1, 2, 3]).build::<8>().unwrap();
+        let mut raw = classic(0x123, &[1, 2, 3]).build::<64>().unwrap().0;
+        // Padding beyond the DLC, timestamp and filter index
+        raw.data[3..].fill(0xff);
+        raw.header[1] |= 0x7f00_ffff;
+        let received = rx::Message(raw);
+        assert_eq!(sent, received);
+        assert_eq!(received, sent);
+        assert_eq!(sent, classic(0x123, &[1, 2, 3]).build::<12>().unwrap());
+    }
+
+    #[test]
+    fn frame_eq_detects_differences() {
+        let sent = classic(0x123, &[1, 2, 3]).build::<8>().unwrap();
+        assert_ne!(sent, classic(0x124, &[1, 2, 3]).build::<8>().unwrap());
+        assert_ne!(sent, classic(0x123, &[1, 2]).build::<8>().unwrap());
+        assert_ne!(sent, classic(0x123, &[1, 2, 4]).build::<8>().unwrap());
+        let fd = tx::MessageBuilder {
+            id: StandardId::new(0x123).unwrap().into(),
+            frame_type: tx::FrameType::FlexibleDatarate {
+                payload: &[1, 2, 3],
+                bit_rate_switching: false,
+                force_error_state_indicator: false,
+            },
+            store_tx_event: None,
+        }
+        .build::<8>()
+        .unwrap();
+        assert_ne!(sent, fd);
+    }
+
+    #[test]
+    fn tx_event_is_toggled_on_built_messages() {
+        let message = classic(0x123, &[1, 2, 3]).build::<8>().unwrap();
+        assert_eq!(message.tx_event(), None);
+        let message = message.with_tx_event(Some(0xa5));
+        assert_eq!(message.tx_event(), Some(0xa5));
+        assert_eq!(message.with_tx_event(Some(3)).tx_event(), Some(3));
+        let message = message.with_tx_event(None);
+        assert_eq!(message.tx_event(), None);
+        assert_eq!(message, classic(0x123, &[1, 2, 3]).build::<8>().unwrap());
+        assert_eq!(
+            message.0.header,
+            classic(0x123, &[1, 2, 3]).build::<8>().unwrap().0.header
+        );
+    }
+
+    #[test]
+    fn encoded_header_matches_built_message() {
+        let payload = [0xa5; 20];
+        let fd = || tx::MessageBuilder {
+            id: StandardId::new(0x123).unwrap().into(),
+            frame_type: tx::FrameType::FlexibleDatarate {
+                payload: &payload,
+                bit_rate_switching: true,
+                force_error_state_indicator: false,
+            },
+            store_tx_event: Some(7),
+        };
+        let (header, encoded_payload) = fd().encode().unwrap();
+        assert_eq!(header, fd().build::<64>().unwrap().0.header);
+        assert_eq!(encoded_payload, &payload);
+        assert!(fd().build::<16>().is_err());
+        let too_long = [0; 65];
+        let oversized = tx::MessageBuilder {
+            id: StandardId::new(0x123).unwrap().into(),
+            frame_type: tx::FrameType::Classic(tx::ClassicFrameType::Data(&too_long)),
+            store_tx_event: None,
+        };
+        assert!(oversized.encode().is_err());
+    }
+
+    #[test]
+    fn tx_event_type_is_decoded() {
+        let event = |efc: u32| {
+            TxEvent(RawMessage {
+                header: [0, efc << 22 | 0xa5 << 24 | 0x1234],
+                data: [],
+            })
+        };
+        assert_eq!(event(1).event_type(), TxEventType::TxEvent);
+        assert_eq!(event(2).event_type(), TxEventType::TxInSpiteOfCancellation);
+        assert_eq!(event(0).event_type(), TxEventType::Reserved);
+        assert_eq!(event(2).message_marker(), 0xa5);
+        assert_eq!(event(2).timestamp(), 0x1234);
+    }
+
+    #[test]
+    fn fd_message_as_classic() {
+        let fd = |payload| tx::MessageBuilder {
+            id: StandardId::new(0x123).unwrap().into(),
+            frame_type: tx::FrameType::FlexibleDatarate {
+                payload,
+                bit_rate_switching: true,
+                force_error_state_indicator: true,
+            },
+            store_tx_event: None,
+        };
+        let classic_message = fd(&[1, 2, 3]).build::<8>().unwrap().as_classic().unwrap();
+        assert!(!classic_message.fd_format());
+        assert!(!classic_message.bit_rate_switching());
+        assert!(!classic_message.is_transmitter_error_passive());
+        assert_eq!(
+            classic_message.0.header,
+            classic(0x123, &[1, 2, 3]).build::<8>().unwrap().0.header
+        );
+        assert!(fd(&[0; 12]).build::<12>().unwrap().as_classic().is_err());
+    }
+
+    #[test]
+    fn worst_case_frame_bits_of_classic_frames() {
+        assert_eq!(worst_case_frame_bits(0, false), (80, 0));
+        assert_eq!(worst_case_frame_bits(8, false), (160, 0));
+        // DLC values above 8 encode 8 bytes in classic frames
+        assert_eq!(worst_case_frame_bits(15, false), (160, 0));
+    }
+
+    #[test]
+    fn worst_case_frame_bits_of_fd_frames() {
+        assert_eq!(worst_case_frame_bits(0, true), (57, 5 + 1 + 27));
+        assert_eq!(worst_case_frame_bits(15, true), (57, 517 + 129 + 32));
+    }
+
+    #[test]
+    fn classic_dlc_above_8_decodes_to_8_bytes() {
+        for dlc in 9..=15 {
+            let message = rx::Message(RawMessage::<8> {
+                header: [0, dlc << 16],
+                data: [0; 8],
+            });
+            assert_eq!(message.dlc(), dlc as u8);
+            assert_eq!(message.decoded_dlc(), 8);
+            assert_eq!(message.data().len(), 8);
+        }
+    }
+
+    #[test]
+    fn fd_payload_is_padded_to_next_supported_length() {
+        let payload = [0xff; 64];
+        let fd = |len: usize| {
+            tx::MessageBuilder {
+                id: StandardId::new(0x123).unwrap().into(),
+                frame_type: tx::FrameType::FlexibleDatarate {
+                    payload: &payload[..len],
+                    bit_rate_switching: false,
+                    force_error_state_indicator: false,
+                },
+                store_tx_event: None,
+            }
+            .build::<64>()
+            .unwrap()
+        };
+        let supported = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+        for len in 0..=64 {
+            let padded_len = *supported.iter().find(|&&s| s >= len).unwrap();
+            let message = fd(len);
+            assert_eq!(message.decoded_dlc(), padded_len, "length {}", len);
+            assert_eq!(&message.data()[..len], &payload[..len]);
+            assert!(message.data()[len..].iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn rx_fd_format() {
+        let frame = |frame_type| {
+            let tx = tx::MessageBuilder {
+                id: StandardId::new(0x123).unwrap().into(),
+                frame_type,
+                store_tx_event: None,
+            }
+            .build::<8>()
+            .unwrap();
+            // The header bits that are set by the transmitter are the same in
+            // RX elements, as if the frame was received through loopback
+            rx::Message(tx.0)
+        };
+        let classic = frame(tx::FrameType::Classic(tx::ClassicFrameType::Data(&[1])));
+        assert!(!classic.fd_format());
+        let fd = frame(tx::FrameType::FlexibleDatarate {
+            payload: &[1],
+            bit_rate_switching: false,
+            force_error_state_indicator: false,
+        });
+        assert!(fd.fd_format());
+        assert!(!fd.bit_rate_switching());
+    }
+
+    #[test]
+    fn rx_validate_length() {
+        use rx::AnyMessage as _;
+        let element = |header_0, header_1| {
+            rx::Message(RawMessage::<8> {
+                header: [header_0, header_1],
+                data: [0; 8],
+            })
+        };
+        const RTR: u32 = 1 << 29;
+        const FDF: u32 = 1 << 21;
+        const BRS: u32 = 1 << 20;
+        let dlc = |dlc: u32| dlc << 16;
+        assert_eq!(element(0, dlc(8)).validate_length(), Ok(()));
+        assert_eq!(element(0, FDF | BRS | dlc(8)).validate_length(), Ok(()));
+        // Classic data length codes above 8 encode 8 bytes
+        assert_eq!(element(0, dlc(15)).validate_length(), Ok(()));
+        assert_eq!(
+            element(RTR, FDF | dlc(1)).validate_length(),
+            Err(LengthError::RemoteFd)
+        );
+        assert_eq!(
+            element(0, BRS | dlc(1)).validate_length(),
+            Err(LengthError::BitRateSwitchWithoutFd)
+        );
+        assert_eq!(
+            element(0, FDF | dlc(9)).validate_length(),
+            Err(LengthError::Truncated {
+                len: 12,
+                capacity: 8
+            })
+        );
+        // Remote frames carry no payload
+        assert_eq!(element(RTR, dlc(8)).validate_length(), Ok(()));
+    }
+
+    #[test]
+    fn rx_data_excludes_padding() {
+        let element = |header_1| {
+            rx::Message(RawMessage::<12> {
+                header: [0, header_1],
+                data: core::array::from_fn(|i| i as u8),
+            })
+        };
+        const FDF: u32 = 1 << 21;
+        assert_eq!(element(3 << 16).data(), &[0, 1, 2]);
+        assert_eq!(element(FDF | (9 << 16)).data().len(), 12);
+        // Clamped to the element size
+        assert_eq!(element(FDF | (10 << 16)).data().len(), 12);
+        // Remote frames carry no payload
+        assert!(rx::Message(RawMessage::<12> {
+            header: [1 << 29, 3 << 16],
+            data: [0; 12],
+        })
+        .data()
+        .is_empty());
+    }
+
+    #[test]
+    fn format_filter() {
+        let element = |header_1| {
+            rx::Message(RawMessage::<8> {
+                header: [0, header_1],
+                data: [0; 8],
+            })
+        };
+        let classic = element(8 << 16);
+        let fd = element(1 << 21 | 8 << 16);
+        assert!(FrameFormatFilter::Any.accepts(&classic));
+        assert!(FrameFormatFilter::Any.accepts(&fd));
+        assert!(FrameFormatFilter::ClassicOnly.accepts(&classic));
+        assert!(!FrameFormatFilter::ClassicOnly.accepts(&fd));
+        assert!(!FrameFormatFilter::FdOnly.accepts(&classic));
+        assert!(FrameFormatFilter::FdOnly.accepts(&fd));
+    }
+
+    #[test]
+    fn rx_filter_index() {
+        use rx::AnyMessage as _;
+        let with_header_1 = |header_1| {
+            rx::Message(RawMessage::<8> {
+                header: [0, header_1],
+                data: [0; 8],
+            })
+        };
+        assert_eq!(with_header_1(5 << 24).filter_index(), Some(5));
+        assert_eq!(with_header_1(0x7f << 24).filter_index(), Some(0x7f));
+        let non_matching = with_header_1(1 << 31 | 5 << 24);
+        assert!(non_matching.accepted_non_matching_frame());
+        assert_eq!(non_matching.filter_index(), None);
+    }
+
+    #[test]
+    fn truncated_rx_payload() {
+        use rx::AnyMessage as _;
+        let fd_header = |dlc: u32| [0, dlc << 16 | 1 << 21];
+        let fits = rx::Message(RawMessage::<8> {
+            header: fd_header(8),
+            data: [0; 8],
+        });
+        assert!(!fits.truncated());
+        let truncated = rx::Message(RawMessage::<8> {
+            header: fd_header(15),
+            data: [0; 8],
+        });
+        assert!(truncated.truncated());
+        assert_eq!(truncated.data().len(), 8);
+    }
+
+    #[test]
+    fn standard_id_boundaries() {
+        assert!(StandardId::new(0x7ff).is_some());
+        assert!(StandardId::new(0x800).is_none());
+        for raw in [0, 0x7ff] {
+            let id = Id::Standard(StandardId::new(raw).unwrap());
+            assert_eq!(roundtrip(id), id);
+        }
+    }
+
+    #[test]
+    fn extended_id_boundaries() {
+        assert!(ExtendedId::new(0x1fff_ffff).is_some());
+        assert!(ExtendedId::new(0x2000_0000).is_none());
+        assert!(ExtendedId::new(u32::MAX).is_none());
+        for raw in [0, 0x1fff_ffff] {
+            let id = Id::Extended(ExtendedId::new(raw).unwrap());
+            assert_eq!(roundtrip(id), id);
+        }
+    }
+}
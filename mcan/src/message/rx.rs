@@ -9,15 +9,56 @@ pub trait AnyMessage: super::AnyMessage {
     fn as_tx_builder(&'_ self) -> tx::MessageBuilder<'_>;
 
     /// Timestamp counter value captured on start of frame reception
+    ///
+    /// The counter is selected by [`Timestamp::select`]; with an external
+    /// source, this is a value of the external counter.
+    ///
+    /// [`Timestamp::select`]: crate::config::Timestamp::select
     fn timestamp(&self) -> u16;
 
     /// Index of the filter that accepted the frame. `None` if no filter
     /// matched, but the message was accepted due to peripheral-wide settings.
+    ///
+    /// The index refers to the standard ID filters or to the extended ID
+    /// filters depending on [`Raw::is_extended`], and equals the index
+    /// returned by [`Filters::push`] for that filter.
+    ///
+    /// [`Filters::push`]: crate::filter::Filters::push
     fn filter_index(&self) -> Option<u8>;
 
     /// `true` if no filter matched, but the message was accepted due to
     /// peripheral-wide settings. See also [`Self::filter_index`]
+    ///
+    /// This is the ANMF bit of the element. It tells frames that fell
+    /// through to [`CanConfig::global_filter`], e.g. while logging all
+    /// traffic with [`Can::set_promiscuous`], apart from frames that an
+    /// explicit filter was set up for.
+    ///
+    /// [`CanConfig::global_filter`]: crate::config::CanConfig::global_filter
+    /// [`Can::set_promiscuous`]: crate::bus::Can::set_promiscuous
     fn accepted_non_matching_frame(&self) -> bool;
+
+    /// `true` if the frame carried more data than fits the element, in which
+    /// case the excess bytes were discarded by the peripheral and
+    /// [`Raw::data`] is incomplete.
+    ///
+    /// This happens when the element size configured in [`Capacities`] is
+    /// smaller than the payload of received CAN FD frames.
+    ///
+    /// [`Capacities`]: crate::messageram::Capacities
+    fn truncated(&self) -> bool;
+
+    /// Checks that the data length code and frame format flags of the element
+    /// are consistent and that the payload fits the element.
+    ///
+    /// Classic frames with a data length code above 8 are legal and carry 8
+    /// bytes, so they pass the check.
+    ///
+    /// The peripheral only stores frames that passed its checks, so a failure
+    /// points at a corrupted element, e.g. one that was read while being
+    /// overwritten, or at an element size that is too small for the bus, see
+    /// [`Self::truncated`].
+    fn validate_length(&self) -> Result<(), LengthError>;
 }
 
 impl<const N: usize> super::AnyMessage for Message<N>
@@ -98,9 +139,30 @@ where
     fn accepted_non_matching_frame(&self) -> bool {
         self.0.header[1] & (1 << 31) != 0 // ANMF
     }
+
+    fn truncated(&self) -> bool {
+        !self.is_remote_frame() && self.decoded_dlc() > N
+    }
+
+    fn validate_length(&self) -> Result<(), LengthError> {
+        if self.fd_format() {
+            if self.is_remote_frame() {
+                return Err(LengthError::RemoteFd);
+            }
+        } else if self.bit_rate_switching() {
+            return Err(LengthError::BitRateSwitchWithoutFd);
+        }
+        if self.truncated() {
+            return Err(LengthError::Truncated {
+                len: self.decoded_dlc(),
+                capacity: N,
+            });
+        }
+        Ok(())
+    }
 }
 
 /// RX message in the peripheral's representation
 #[repr(transparent)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct Message<const N: usize>(pub(super) RawMessage<N>);
@@ -7,6 +7,9 @@ use super::*;
 pub trait AnyMessage: super::AnyMessage {
     /// Constructs the message described by `m`
     fn new(m: MessageBuilder) -> Result<Self, TooMuchData>;
+
+    /// See [`Message::with_tx_event`]
+    fn with_tx_event(self, marker: Option<u8>) -> Self;
 }
 
 impl<const N: usize> super::AnyMessage for Message<N>
@@ -53,6 +56,10 @@ where
     fn new(m: MessageBuilder) -> Result<Self, TooMuchData> {
         m.build()
     }
+
+    fn with_tx_event(self, marker: Option<u8>) -> Self {
+        Message::with_tx_event(self, marker)
+    }
 }
 
 /// TX message in the peripheral's representation
@@ -60,6 +67,46 @@ where
 #[derive(Copy, Clone, Debug)]
 pub struct Message<const N: usize>(pub(super) RawMessage<N>);
 
+impl<const N: usize> Message<N> {
+    /// Returns the marker of the event stored in the TX event queue on
+    /// transmission, or `None` if no event is stored.
+    pub fn tx_event(&self) -> Option<u8> {
+        let t1 = self.0.header[1];
+        (t1 & (1 << 23) != 0).then_some((t1 >> 24) as u8)
+    }
+
+    /// Converts the message into a classic CAN frame by clearing its FD
+    /// format, bit rate switching and error state indicator bits.
+    ///
+    /// The frame format is chosen per message, so classic frames can be sent
+    /// to legacy nodes while the peripheral operates in [`Mode::Fd`]. Fails if
+    /// the payload does not fit a classic frame.
+    ///
+    /// [`Mode::Fd`]: crate::config::Mode::Fd
+    pub fn as_classic(mut self) -> Result<Self, TooMuchData> {
+        if self.0.dlc() > 8 {
+            return Err(TooMuchData);
+        }
+        self.0.header[0] &= !(1 << 31);
+        self.0.header[1] &= !((1 << 20) | (1 << 21));
+        Ok(self)
+    }
+
+    /// Sets whether transmitting this message stores an event identified by
+    /// `marker` in the TX event queue, see [`MessageBuilder::store_tx_event`].
+    ///
+    /// This allows reusing an already built message while only requesting
+    /// events for the transmissions of interest.
+    pub fn with_tx_event(mut self, marker: Option<u8>) -> Self {
+        let t1 = &mut self.0.header[1];
+        *t1 &= !0xff80_0000;
+        if let Some(mm) = marker {
+            *t1 |= (1 << 23) | ((mm as u32) << 24);
+        }
+        self
+    }
+}
+
 /// Selects the type of the Classic CAN frame.
 pub enum ClassicFrameType<'a> {
     /// 0-8 byte message payload
@@ -82,6 +129,10 @@ pub enum FrameType<'a> {
         /// 0-64 byte message payload. The payload must not be bigger than the
         /// maximum payload size chosen in [`Capacities::TxMessage`].
         ///
+        /// CAN FD frames only support payloads of 0 to 8, 12, 16, 20, 24, 32,
+        /// 48 and 64 bytes. Payloads of other lengths are zero-padded to the
+        /// next supported length.
+        ///
         /// [`Capacities::TxMessage`]: crate::messageram::Capacities
         payload: &'a [u8],
         /// Parts of the frame are transmitted at a higher bit rate. Note that
@@ -99,6 +150,9 @@ pub enum FrameType<'a> {
 /// representation the peripheral understands.
 pub struct MessageBuilder<'a> {
     /// CAN identifier for the frame
+    ///
+    /// Use [`StandardId::new`] or [`ExtendedId::new`] to construct a range
+    /// checked identifier from a raw value.
     pub id: Id,
     /// Message frame type with a payload
     pub frame_type: FrameType<'a>,
@@ -110,41 +164,42 @@ pub struct MessageBuilder<'a> {
 impl<'a> MessageBuilder<'a> {
     /// Create the message in the format required by the peripheral.
     pub fn build<const N: usize>(self) -> Result<Message<N>, TooMuchData> {
+        let (header, payload) = self.encode()?;
+        if payload.len() > N {
+            return Err(TooMuchData);
+        }
         let mut data = [0; N];
+        data[..payload.len()].copy_from_slice(payload);
+        Ok(Message(RawMessage { header, data }))
+    }
 
-        let mut copy_payload = |d: &[u8]| {
-            if d.len() > N {
-                return Err(TooMuchData);
-            }
-            data[..d.len()].copy_from_slice(d);
-            Ok(())
-        };
-
+    /// Returns the header words of the element and the payload to be placed
+    /// after them. Bytes between the end of the payload and the length
+    /// encoded in the header must be zero.
+    pub(crate) fn encode(self) -> Result<([u32; 2], &'a [u8]), TooMuchData> {
         let id_field = match self.id {
             Id::Standard(id) => (id.as_raw() as u32) << 18,
             Id::Extended(id) => id.as_raw(),
         };
         let xtd = matches!(self.id, Id::Extended(_));
-        let (fdf, brs, esi, rtr, len) = match self.frame_type {
-            FrameType::Classic(payload) => {
-                let (rtr, len) = match payload {
-                    ClassicFrameType::Data(payload) => {
-                        copy_payload(payload)?;
-                        (false, payload.len())
-                    }
-                    ClassicFrameType::Remote { desired_len } => (true, desired_len),
-                };
-                (false, false, false, rtr, len)
-            }
+        let (fdf, brs, esi, rtr, len, payload) = match self.frame_type {
+            FrameType::Classic(payload) => match payload {
+                ClassicFrameType::Data(payload) => {
+                    (false, false, false, false, payload.len(), payload)
+                }
+                ClassicFrameType::Remote { desired_len } => {
+                    (false, false, false, true, desired_len, &[][..])
+                }
+            },
             FrameType::FlexibleDatarate {
                 payload,
                 bit_rate_switching: brs,
                 force_error_state_indicator: esi,
-            } => {
-                copy_payload(payload)?;
-                (true, brs, esi, false, payload.len())
-            }
+            } => (true, brs, esi, false, payload.len(), payload),
         };
+        if payload.len() > 64 {
+            return Err(TooMuchData);
+        }
         let dlc = len_to_dlc(len, fdf)?;
         let efc = self.store_tx_event.is_some();
         let mm = self.store_tx_event.unwrap_or(0);
@@ -155,9 +210,6 @@ impl<'a> MessageBuilder<'a> {
             | ((fdf as u32) << 21)
             | ((efc as u32) << 23)
             | ((mm as u32) << 24);
-        Ok(Message(RawMessage {
-            header: [t0, t1],
-            data,
-        }))
+        Ok(([t0, t1], payload))
     }
 }
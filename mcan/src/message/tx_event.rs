@@ -45,6 +45,16 @@ impl TxEvent {
         (self.0.header[1] >> 24) as u8
     }
 
+    /// Timestamp counter value captured on start of frame transmission
+    ///
+    /// The counter is selected by [`Timestamp::select`], like for received
+    /// messages.
+    ///
+    /// [`Timestamp::select`]: crate::config::Timestamp::select
+    pub fn timestamp(&self) -> u16 {
+        self.0.header[1] as u16
+    }
+
     /// Parse the event type field. Indicates whether cancellation was requested
     /// at the time transmission succeeded.
     pub fn event_type(&self) -> TxEventType {
@@ -54,6 +64,7 @@ impl TxEvent {
 
 /// Indicates whether cancellation was requested at the time transmission
 /// succeeded
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TxEventType {
     /// Unrecognized field value
     Reserved,
@@ -4,10 +4,11 @@
 //!
 //! [`crate`]: crate#message-ram-configuration
 use crate::filter::{FilterExtendedId, FilterStandardId};
-use crate::message::{rx, tx, TxEvent};
+use crate::message::{rx, tx, AnyMessage, TxEvent};
 use core::mem::MaybeUninit;
+use fugit::{HertzU32, MicrosDurationU32};
 use generic_array::{
-    typenum::{consts::*, IsLessOrEqual, LeEq, Same},
+    typenum::{consts::*, IsLessOrEqual, LeEq, Same, Unsigned},
     ArrayLength, GenericArray,
 };
 use vcell::VolatileCell;
@@ -24,11 +25,11 @@ pub trait Capacities {
     type DedicatedRxBuffers: LimitedArrayLength<VolatileCell<Self::RxBufferMessage>, U64>;
     /// [`rx::Message`] with size selected for use in receive FIFO 0
     type RxFifo0Message: rx::AnyMessage;
-    /// Receive FIFO0 size
+    /// Receive FIFO0 size; `U0` disables the FIFO
     type RxFifo0: LimitedArrayLength<VolatileCell<Self::RxFifo0Message>, U64>;
     /// [`rx::Message`] with size selected for use in receive FIFO 1
     type RxFifo1Message: rx::AnyMessage;
-    /// Receive FIFO1 size
+    /// Receive FIFO1 size; `U0` disables the FIFO
     type RxFifo1: LimitedArrayLength<VolatileCell<Self::RxFifo1Message>, U64>;
     /// [`tx::Message`] with size selected for use in transmit buffers
     type TxMessage: tx::AnyMessage;
@@ -39,6 +40,134 @@ pub trait Capacities {
     type DedicatedTxBuffers: LimitedArrayLength<VolatileCell<Self::TxMessage>, Self::TxBuffers>;
     /// Transmit event FIFO size
     type TxEventFifo: LimitedArrayLength<VolatileCell<TxEvent>, U32>;
+
+    /// Checks for element sizes that are valid, but likely not intended.
+    ///
+    /// Received frames that do not fit an element are silently truncated, so
+    /// mixing element sizes is easy to get wrong. Only sections with a
+    /// non-zero capacity are taken into account.
+    fn validate() -> Result<(), CapacitiesError> {
+        let rx_sizes = [
+            (Self::RxFifo0::USIZE, Self::RxFifo0Message::REG),
+            (Self::RxFifo1::USIZE, Self::RxFifo1Message::REG),
+            (Self::DedicatedRxBuffers::USIZE, Self::RxBufferMessage::REG),
+        ];
+        let mut used = rx_sizes
+            .iter()
+            .filter(|(capacity, _)| *capacity > 0)
+            .map(|&(_, reg)| data_size(reg));
+        let Some(first) = used.next() else {
+            return Ok(());
+        };
+        let (min, max) = used.fold((first, first), |(min, max), size| {
+            (min.min(size), max.max(size))
+        });
+        if min != max {
+            return Err(CapacitiesError::RxElementSizeMismatch { min, max });
+        }
+        let tx = data_size(Self::TxMessage::REG);
+        if Self::TxBuffers::USIZE > 0 && tx > max {
+            return Err(CapacitiesError::TxLargerThanRx { tx, rx: max });
+        }
+        if Self::TxBuffers::USIZE > 0 && tx < max {
+            return Err(CapacitiesError::TxSmallerThanRx { tx, rx: max });
+        }
+        Ok(())
+    }
+}
+
+/// Likely mistakes in element sizes, detected by [`Capacities::validate`]
+///
+/// All sizes are in bytes of data per element.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CapacitiesError {
+    /// The RX FIFOs and dedicated RX buffers in use do not share a common
+    /// element size
+    RxElementSizeMismatch {
+        /// Smallest element size
+        min: usize,
+        /// Largest element size
+        max: usize,
+    },
+    /// TX elements can hold more data than any RX element, so equally sized
+    /// frames from other nodes would be truncated
+    TxLargerThanRx {
+        /// TX element size
+        tx: usize,
+        /// Largest RX element size
+        rx: usize,
+    },
+    /// RX elements can hold more data than TX elements, so received frames
+    /// cannot be looped back or forwarded without truncating them
+    TxSmallerThanRx {
+        /// TX element size
+        tx: usize,
+        /// Largest RX element size
+        rx: usize,
+    },
+}
+
+/// Reasons a raw memory region cannot hold a [`SharedMemory`], reported by
+/// [`SharedMemory::from_raw`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum AddressError {
+    /// The region is shorter than the memory required by the capacities
+    TooSmall {
+        /// Required size in bytes
+        required: usize,
+        /// Size of the region in bytes
+        len: usize,
+    },
+    /// The start of the region is not sufficiently aligned
+    Misaligned,
+    /// The region crosses a 64 KiB boundary, so it cannot be reached with the
+    /// 16-bit addressing of the peripheral
+    NotAddressable,
+}
+
+/// Estimates the RX FIFO depth needed to not lose frames while the firmware
+/// takes up to `service_latency` to start draining the FIFO.
+///
+/// Frames are assumed to arrive back to back at `bus_bitrate` with the
+/// shortest possible length: a classic data frame with a standard ID, no
+/// data and no stuff bits, followed by the interframe space, which takes 47
+/// bit times. At most `max_burst_frames` frames are expected in a row, so
+/// the depth never exceeds that.
+///
+/// A FIFO holds at most 64 elements. Larger results mean that the service
+/// latency has to be reduced, or that frames have to be spread over both
+/// FIFOs with filters.
+///
+/// ```
+/// # use mcan::messageram::recommend_fifo_depth;
+/// # use fugit::{ExtU32 as _, RateExtU32 as _};
+/// // At 500 kbit/s, a frame takes at least 94 µs, so up to 11 frames arrive
+/// // within 1 ms
+/// assert_eq!(recommend_fifo_depth(500.kHz(), 32, 1.millis()), 11);
+/// // No more than the longest burst has to be stored
+/// assert_eq!(recommend_fifo_depth(500.kHz(), 8, 1.millis()), 8);
+/// // 10 ms at 1 Mbit/s exceed what a single FIFO can hold
+/// assert_eq!(recommend_fifo_depth(1.MHz(), 1000, 10.millis()), 213);
+/// ```
+pub fn recommend_fifo_depth(
+    bus_bitrate: HertzU32,
+    max_burst_frames: usize,
+    service_latency: MicrosDurationU32,
+) -> usize {
+    const MIN_FRAME_BITS: u64 = 47;
+    let bits = u64::from(service_latency.to_micros()) * u64::from(bus_bitrate.to_Hz());
+    let frames = bits.div_ceil(MIN_FRAME_BITS * 1_000_000);
+    usize::try_from(frames).map_or(max_burst_frames, |frames| frames.min(max_burst_frames))
+}
+
+/// Converts the data field size configuration value to bytes
+fn data_size(reg: u8) -> usize {
+    match reg {
+        0..=4 => 8 + 4 * reg as usize,
+        5 => 32,
+        6 => 48,
+        _ => 64,
+    }
 }
 
 /// [`generic_array::ArrayLength`] with an upper bound.
@@ -68,6 +197,13 @@ pub(super) struct SharedMemoryInner<C: Capacities> {
 pub struct SharedMemory<C: Capacities>(MaybeUninit<SharedMemoryInner<C>>);
 
 impl<C: Capacities> SharedMemory<C> {
+    /// Size of the memory region in bytes, e.g. to check it against the
+    /// region reserved in a linker script
+    pub const SIZE: usize = core::mem::size_of::<Self>();
+
+    /// Required alignment of the memory region in bytes
+    pub const ALIGN: usize = core::mem::align_of::<Self>();
+
     pub(super) fn init(&mut self) -> &mut SharedMemoryInner<C> {
         self.0 = MaybeUninit::zeroed();
         // Safety: All bits 0 is a valid value for all the contained arrays.
@@ -81,13 +217,309 @@ impl<C: Capacities> SharedMemory<C> {
         Self(MaybeUninit::uninit())
     }
 
+    /// Reinterprets a runtime-chosen memory region as `SharedMemory`, e.g. a
+    /// region reserved by a previous boot stage.
+    ///
+    /// The region must be large enough for the capacities `C`, aligned for
+    /// `SharedMemory<C>` and must not cross a 64 KiB boundary. The latter is
+    /// necessary but not sufficient for the memory to be usable:
+    /// [`CanConfigurable::new`] still verifies that the region lies within
+    /// the 64 KiB following
+    /// [`Dependencies::eligible_message_ram_start`].
+    ///
+    /// The content of the region does not matter, it is zeroed when the
+    /// memory is taken into use.
+    ///
+    /// # Safety
+    /// - `ptr` must be valid for reads and writes of `len` bytes for the
+    ///   whole lifetime `'a`
+    /// - The region must not be accessed through any other pointer or
+    ///   reference during `'a`, neither by the core nor by other bus masters
+    ///   such as DMA
+    ///
+    /// [`CanConfigurable::new`]: crate::bus::CanConfigurable::new
+    /// [`Dependencies::eligible_message_ram_start`]: mcan_core::Dependencies::eligible_message_ram_start
+    pub unsafe fn from_raw<'a>(ptr: *mut u8, len: usize) -> Result<&'a mut Self, AddressError> {
+        Self::check_region(ptr as usize, len)?;
+        // Safety: The region is large enough and aligned, and the caller
+        // guarantees validity and exclusive access. `SharedMemory` wraps a
+        // `MaybeUninit`, so any content is a valid value.
+        Ok(&mut *(ptr as *mut Self))
+    }
+
+    /// Checks the region starting at address `start` against the
+    /// requirements of [`Self::from_raw`] without accessing it.
+    fn check_region(start: usize, len: usize) -> Result<(), AddressError> {
+        let required = Self::SIZE;
+        if len < required {
+            return Err(AddressError::TooSmall { required, len });
+        }
+        if !start.is_multiple_of(Self::ALIGN) {
+            return Err(AddressError::Misaligned);
+        }
+        if required > 0 && start >> 16 != (start + required - 1) >> 16 {
+            return Err(AddressError::NotAddressable);
+        }
+        Ok(())
+    }
+
     /// The peripheral uses 16-bit addressing for its memory configuration,
     /// offset from the start of system RAM. If `SharedMemory` is allocated
     /// outside the addressable region, it cannot be used.
     pub(crate) fn is_addressable(&self, eligible_message_ram_start: *const ()) -> bool {
         let eligible_message_ram_start = eligible_message_ram_start as usize;
         let start = self as *const _ as usize;
-        let end_exclusive = start + core::mem::size_of::<Self>();
+        let end_exclusive = start + Self::SIZE;
         eligible_message_ram_start <= start && end_exclusive - eligible_message_ram_start <= 1 << 16
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::mem::size_of;
+
+    /// Deterministic setup without FIFOs, using only dedicated buffers
+    struct DedicatedOnly;
+    impl Capacities for DedicatedOnly {
+        type StandardFilters = U8;
+        type ExtendedFilters = U0;
+        type RxBufferMessage = rx::Message<8>;
+        type DedicatedRxBuffers = U8;
+        type RxFifo0Message = rx::Message<8>;
+        type RxFifo0 = U0;
+        type RxFifo1Message = rx::Message<8>;
+        type RxFifo1 = U0;
+        type TxMessage = tx::Message<8>;
+        type TxBuffers = U4;
+        type DedicatedTxBuffers = U4;
+        type TxEventFifo = U0;
+    }
+
+    #[test]
+    fn dedicated_only_is_valid() {
+        assert_eq!(DedicatedOnly::validate(), Ok(()));
+    }
+
+    #[test]
+    fn empty_sections_take_no_space() {
+        /// Only a TX queue, without filters, RX buffers or TX events
+        struct TxOnly;
+        impl Capacities for TxOnly {
+            type StandardFilters = U0;
+            type ExtendedFilters = U0;
+            type RxBufferMessage = rx::Message<8>;
+            type DedicatedRxBuffers = U0;
+            type RxFifo0Message = rx::Message<8>;
+            type RxFifo0 = U0;
+            type RxFifo1Message = rx::Message<8>;
+            type RxFifo1 = U0;
+            type TxMessage = tx::Message<8>;
+            type TxBuffers = U1;
+            type DedicatedTxBuffers = U0;
+            type TxEventFifo = U0;
+        }
+        assert_eq!(TxOnly::validate(), Ok(()));
+        assert_eq!(SharedMemory::<TxOnly>::SIZE, 8 + 8);
+    }
+
+    #[test]
+    fn size_is_sum_of_elements() {
+        // 8 standard filters, 8 RX buffers and 4 TX buffers
+        let expected = 8 * 4 + 8 * (8 + 8) + 4 * (8 + 8);
+        assert_eq!(SharedMemory::<DedicatedOnly>::SIZE, expected);
+        assert_eq!(SharedMemory::<DedicatedOnly>::ALIGN, 4);
+    }
+
+    #[test]
+    fn mismatched_element_sizes_are_flagged() {
+        struct Mixed;
+        impl Capacities for Mixed {
+            type StandardFilters = U0;
+            type ExtendedFilters = U0;
+            type RxBufferMessage = rx::Message<64>;
+            type DedicatedRxBuffers = U0;
+            type RxFifo0Message = rx::Message<8>;
+            type RxFifo0 = U4;
+            type RxFifo1Message = rx::Message<64>;
+            type RxFifo1 = U4;
+            type TxMessage = tx::Message<64>;
+            type TxBuffers = U4;
+            type DedicatedTxBuffers = U0;
+            type TxEventFifo = U0;
+        }
+        assert_eq!(
+            Mixed::validate(),
+            Err(CapacitiesError::RxElementSizeMismatch { min: 8, max: 64 })
+        );
+    }
+
+    #[test]
+    fn tx_larger_than_rx_is_flagged() {
+        struct LargeTx;
+        impl Capacities for LargeTx {
+            type StandardFilters = U0;
+            type ExtendedFilters = U0;
+            type RxBufferMessage = rx::Message<64>;
+            type DedicatedRxBuffers = U0;
+            type RxFifo0Message = rx::Message<8>;
+            type RxFifo0 = U4;
+            type RxFifo1Message = rx::Message<8>;
+            type RxFifo1 = U4;
+            type TxMessage = tx::Message<64>;
+            type TxBuffers = U4;
+            type DedicatedTxBuffers = U0;
+            type TxEventFifo = U0;
+        }
+        assert_eq!(
+            LargeTx::validate(),
+            Err(CapacitiesError::TxLargerThanRx { tx: 64, rx: 8 })
+        );
+    }
+
+    #[test]
+    fn tx_smaller_than_rx_is_flagged() {
+        struct SmallTx;
+        impl Capacities for SmallTx {
+            type StandardFilters = U0;
+            type ExtendedFilters = U0;
+            type RxBufferMessage = rx::Message<64>;
+            type DedicatedRxBuffers = U0;
+            type RxFifo0Message = rx::Message<64>;
+            type RxFifo0 = U4;
+            type RxFifo1Message = rx::Message<64>;
+            type RxFifo1 = U0;
+            type TxMessage = tx::Message<8>;
+            type TxBuffers = U4;
+            type DedicatedTxBuffers = U0;
+            type TxEventFifo = U0;
+        }
+        assert_eq!(
+            SmallTx::validate(),
+            Err(CapacitiesError::TxSmallerThanRx { tx: 8, rx: 64 })
+        );
+    }
+
+    #[test]
+    fn data_sizes_match_element_types() {
+        assert_eq!(data_size(rx::Message::<8>::REG), 8);
+        assert_eq!(data_size(rx::Message::<12>::REG), 12);
+        assert_eq!(data_size(rx::Message::<16>::REG), 16);
+        assert_eq!(data_size(rx::Message::<20>::REG), 20);
+        assert_eq!(data_size(rx::Message::<24>::REG), 24);
+        assert_eq!(data_size(rx::Message::<32>::REG), 32);
+        assert_eq!(data_size(rx::Message::<48>::REG), 48);
+        assert_eq!(data_size(rx::Message::<64>::REG), 64);
+    }
+
+    #[test]
+    fn region_is_validated() {
+        type Memory = SharedMemory<DedicatedOnly>;
+        let required = Memory::SIZE;
+        assert_eq!(
+            Memory::check_region(0x2000_0000, required - 1),
+            Err(AddressError::TooSmall {
+                required,
+                len: required - 1
+            })
+        );
+        assert_eq!(
+            Memory::check_region(0x2000_0001, required),
+            Err(AddressError::Misaligned)
+        );
+        // Crosses the 64 KiB boundary at 0x2001_0000
+        assert_eq!(
+            Memory::check_region(0x2000_fff0, required),
+            Err(AddressError::NotAddressable)
+        );
+        assert_eq!(Memory::check_region(0x2000_0000, required), Ok(()));
+    }
+
+    #[test]
+    fn from_raw_validates_region() {
+        let required = size_of::<SharedMemory<DedicatedOnly>>();
+        let mut buffer = [0u32; 64];
+        let ptr = buffer.as_mut_ptr() as *mut u8;
+        // Safety: The pointers are derived from `buffer`, which outlives the
+        // returned references and is not accessed otherwise.
+        unsafe {
+            assert_eq!(
+                SharedMemory::<DedicatedOnly>::from_raw(ptr, required - 1).err(),
+                Some(AddressError::TooSmall {
+                    required,
+                    len: required - 1
+                })
+            );
+            assert_eq!(
+                SharedMemory::<DedicatedOnly>::from_raw(ptr.add(1), required).err(),
+                Some(AddressError::Misaligned)
+            );
+        }
+    }
+
+    #[test]
+    fn zero_depth_fifos_take_no_space() {
+        let mut memory = SharedMemory::<DedicatedOnly>::new();
+        let inner = memory.init();
+        assert_eq!(inner.rx_fifo_0.len(), 0);
+        assert_eq!(inner.rx_fifo_1.len(), 0);
+        assert_eq!(inner.tx_event_fifo.len(), 0);
+        assert_eq!(
+            size_of::<SharedMemory<DedicatedOnly>>(),
+            8 * 4 + 8 * 16 + 4 * 16
+        );
+    }
+
+    #[test]
+    fn init_zeroes_stale_content() {
+        struct Full;
+        impl Capacities for Full {
+            type StandardFilters = U4;
+            type ExtendedFilters = U4;
+            type RxBufferMessage = rx::Message<8>;
+            type DedicatedRxBuffers = U2;
+            type RxFifo0Message = rx::Message<8>;
+            type RxFifo0 = U4;
+            type RxFifo1Message = rx::Message<64>;
+            type RxFifo1 = U4;
+            type TxMessage = tx::Message<64>;
+            type TxBuffers = U4;
+            type DedicatedTxBuffers = U0;
+            type TxEventFifo = U4;
+        }
+        /// Bytes of `elements`
+        fn bytes<T>(elements: &[T]) -> &[u8] {
+            // Safety: The elements are plain data without padding.
+            unsafe {
+                core::slice::from_raw_parts(
+                    elements.as_ptr().cast(),
+                    core::mem::size_of_val(elements),
+                )
+            }
+        }
+
+        let mut memory = SharedMemory::<Full>::new();
+        // Stale content, e.g. from before a reset
+        // Safety: `SharedMemory` is `MaybeUninit`, so any content is allowed.
+        unsafe {
+            core::ptr::write_bytes(
+                &mut memory as *mut SharedMemory<Full> as *mut u8,
+                0xa5,
+                SharedMemory::<Full>::SIZE,
+            )
+        };
+        let inner = memory.init();
+        assert!(inner.filters_standard.iter().all(|f| f.get().0 == 0));
+        assert!(inner.filters_extended.iter().all(|f| f.get().0 == [0, 0]));
+        for elements in [
+            bytes(&inner.rx_fifo_0),
+            bytes(&inner.rx_fifo_1),
+            bytes(&inner.rx_dedicated_buffers),
+            bytes(&inner.tx_event_fifo),
+            bytes(&inner.tx_buffers),
+        ] {
+            assert!(!elements.is_empty());
+            assert!(elements.iter().all(|&b| b == 0));
+        }
+    }
+}
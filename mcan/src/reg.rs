@@ -3,6 +3,8 @@
 #![allow(non_camel_case_types)]
 pub mod generic;
 
+use crate::config::WaitStrategy;
+
 /// Blanket implementation trait that provides convenience method for recasting
 /// the pointer type to specific [`RegisterBlock`] type.
 ///
@@ -34,34 +36,128 @@ impl<Id> Can<Id> {
 }
 
 impl<Id: mcan_core::CanId> Can<Id> {
-    fn set_init(&self, value: bool) {
+    fn set_init(&self, value: bool, wait: &dyn WaitStrategy) {
+        // Ensure the peripheral leaves the "power down" mode properly if it was
+        // previously entered.
+        if !value {
+            self.cccr.modify(|_, w| w.csr().clear_bit());
+            while self.cccr.read().csa().bit_is_set() {
+                wait.wait();
+            }
+        }
+
+        self.cccr.modify(|_, w| w.init().bit(value));
+        while self.cccr.read().init().bit() != value {
+            wait.wait();
+        }
+    }
+
+    /// Polls CCCR up to `polls` times until `done` holds, calling `wait`
+    /// after each failed poll. Returns `false` if `done` never held.
+    fn poll_cccr(&self, polls: u32, wait: &dyn WaitStrategy, done: fn(&cccr::R) -> bool) -> bool {
+        for _ in 0..polls {
+            if done(&self.cccr.read()) {
+                return true;
+            }
+            wait.wait();
+        }
+        false
+    }
+
+    /// Like `set_init`, but gives up after polling the register `polls` times
+    /// for each transition. Returns `false` if the requested state was not
+    /// reached in time.
+    fn set_init_timeout(&self, value: bool, polls: u32, wait: &dyn WaitStrategy) -> bool {
+        let poll = |done: fn(&cccr::R) -> bool| self.poll_cccr(polls, wait, done);
         // Ensure the peripheral leaves the "power down" mode properly if it was
         // previously entered.
         if !value {
             self.cccr.modify(|_, w| w.csr().clear_bit());
-            while self.cccr.read().csa().bit_is_set() {}
+            if !poll(|r| r.csa().bit_is_clear()) {
+                return false;
+            }
         }
 
         self.cccr.modify(|_, w| w.init().bit(value));
-        while self.cccr.read().init().bit() != value {}
+        if value {
+            poll(|r| r.init().bit())
+        } else {
+            poll(|r| r.init().bit_is_clear())
+        }
     }
 
-    fn enable_cce(&self) {
+    fn enable_cce(&self, wait: &dyn WaitStrategy) {
         self.cccr.modify(|_, w| w.cce().set_bit());
-        while !self.cccr.read().cce().bit() {}
+        while !self.cccr.read().cce().bit() {
+            wait.wait();
+        }
+    }
+
+    /// Like `enable_cce`, but gives up after polling the register `polls`
+    /// times. Returns `false` if CCE was not set in time.
+    fn enable_cce_timeout(&self, polls: u32, wait: &dyn WaitStrategy) -> bool {
+        self.cccr.modify(|_, w| w.cce().set_bit());
+        self.poll_cccr(polls, wait, |r| r.cce().bit())
+    }
+
+    /// Clears CCE, giving up after polling the register `polls` times.
+    /// Returns `false` if CCE was not cleared in time.
+    fn disable_cce_timeout(&self, polls: u32, wait: &dyn WaitStrategy) -> bool {
+        self.cccr.modify(|_, w| w.cce().clear_bit());
+        self.poll_cccr(polls, wait, |r| r.cce().bit_is_clear())
+    }
+
+    pub(crate) fn configuration_mode(&self, wait: &dyn WaitStrategy) {
+        self.set_init(true, wait);
+        self.enable_cce(wait);
+    }
+
+    /// Enters configuration mode, giving up after `polls` register reads per
+    /// step and calling `wait` in between. Returns `false` if the peripheral
+    /// did not confirm CCE=1, in which case writes to protected registers
+    /// would be ignored.
+    pub(crate) fn try_configuration_mode(&self, polls: u32, wait: &dyn WaitStrategy) -> bool {
+        self.set_init_timeout(true, polls, wait) && self.enable_cce_timeout(polls, wait)
+    }
+
+    /// Temporarily enters configuration mode to run `f`, then restores the
+    /// previous mode of operation. Each mode change gives up after `polls`
+    /// register reads like [`Self::try_configuration_mode`].
+    ///
+    /// Returns `None` without running `f` if configuration mode was not
+    /// confirmed. A peripheral that does not leave initialization mode in time
+    /// afterwards is left there; `f` has been applied in that case.
+    pub(crate) fn reconfigure<T>(
+        &self,
+        polls: u32,
+        wait: &dyn WaitStrategy,
+        f: impl FnOnce(&Self) -> T,
+    ) -> Option<T> {
+        let operational = self.is_operational();
+        let configured = self.try_configuration_mode(polls, wait);
+        let ret = configured.then(|| f(self));
+        if operational {
+            // CCE is cleared automatically when leaving initialization
+            self.try_operational_mode(polls, wait);
+        } else {
+            self.disable_cce_timeout(polls, wait);
+        }
+        ret
     }
 
-    pub(crate) fn configuration_mode(&self) {
-        self.set_init(true);
-        self.enable_cce();
+    pub(crate) fn initialization_mode(&self, wait: &dyn WaitStrategy) {
+        self.set_init(true, wait);
     }
 
-    pub(crate) fn initialization_mode(&self) {
-        self.set_init(true);
+    pub(crate) fn operational_mode(&self, wait: &dyn WaitStrategy) {
+        self.set_init(false, wait);
     }
 
-    pub(crate) fn operational_mode(&self) {
-        self.set_init(false);
+    /// Leaves initialization mode, giving up after `polls` register reads and
+    /// calling `wait` in between. Returns `false` if the peripheral did not
+    /// leave initialization mode.
+    pub(crate) fn try_operational_mode(&self, polls: u32, wait: &dyn WaitStrategy) -> bool {
+        self.set_init_timeout(false, polls, wait)
     }
 
     pub(crate) fn is_operational(&self) -> bool {
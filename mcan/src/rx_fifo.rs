@@ -4,7 +4,8 @@
 //!
 //! [`Action`]: crate::filter::Action
 
-use crate::message::rx;
+use crate::config::RxFifoModeVariant;
+use crate::message::{rx, FrameFormatFilter};
 use crate::reg;
 use core::convert::Infallible;
 use core::marker::PhantomData;
@@ -35,6 +36,12 @@ pub trait DynRxFifo {
     fn is_empty(&self) -> bool;
 
     /// Returns the number of elements the queue can hold
+    ///
+    /// For [`RxFifo`], this is read back from the FIFO size configured in the
+    /// peripheral (RXFnC.FS), which confirms the depth selected in
+    /// [`Capacities`].
+    ///
+    /// [`Capacities`]: crate::messageram::Capacities
     fn capacity(&self) -> usize;
 
     /// Returns a received frame if available. Note that the FIFO also
@@ -47,6 +54,18 @@ pub struct Fifo0;
 /// Value of the type-level FIFO selection enum representing FIFO 1.
 pub struct Fifo1;
 
+/// Identifies the RX FIFO a message was received from at runtime, see
+/// [`Can::drain_rx_fair`].
+///
+/// [`Can::drain_rx_fair`]: crate::bus::Can::drain_rx_fair
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RxFrameSource {
+    /// RX FIFO 0
+    Fifo0,
+    /// RX FIFO 1
+    Fifo1,
+}
+
 /// Provides raw access to the registers controlling the RX FIFO.
 pub trait GetRxFifoRegs {
     /// # Safety
@@ -88,6 +107,145 @@ where
         // Safety: The RxFifo owns the registers.
         unsafe { self.registers() }
     }
+
+    /// Pairs the FIFO with a `handler` that is called for each received
+    /// message by [`MessageHandler::handle_interrupt`].
+    ///
+    /// This standardizes the pattern of draining the FIFO from its new message
+    /// interrupt.
+    pub fn on_message<H: FnMut(M)>(self, handler: H) -> MessageHandler<Self, H> {
+        MessageHandler {
+            fifo: self,
+            handler,
+        }
+    }
+
+    /// Mode of operation the FIFO is configured with (RXFnC.FOM)
+    pub fn mode(&self) -> RxFifoModeVariant {
+        if self.regs().c.read().fom().bit_is_set() {
+            RxFifoModeVariant::Overwrite
+        } else {
+            RxFifoModeVariant::Blocking
+        }
+    }
+
+    /// Returns a copy of the message `offset` positions after the oldest one,
+    /// without removing any message from the FIFO. Returns `None` if the FIFO
+    /// holds no more than `offset` messages.
+    ///
+    /// This allows looking ahead beyond the next message that
+    /// [`DynRxFifo::receive`] would return, which is `get(0)`.
+    pub fn get(&self, offset: usize) -> Option<M> {
+        let status = self.regs().s.read();
+        if offset >= status.ffl().bits() as usize {
+            return None;
+        }
+        let index = (status.fgi().bits() as usize + offset) % self.memory.len();
+        Some(self.memory[index].get())
+    }
+
+    /// Returns a copy of the oldest message without removing it from the
+    /// FIFO, or `None` if the FIFO is empty.
+    ///
+    /// Together with [`Self::release`], this allows consuming messages
+    /// transactionally: the message stays in the FIFO until it was processed
+    /// successfully, whereas [`DynRxFifo::receive`] removes it right away.
+    /// In [`RxFifoModeVariant::Overwrite`] mode, a full FIFO may replace the
+    /// message in between, so this should be used in blocking mode.
+    pub fn read_peek(&self) -> Option<M> {
+        self.get(0)
+    }
+
+    /// Removes the oldest message from the FIFO after it was inspected with
+    /// [`Self::read_peek`]. Returns `false` if the FIFO is empty.
+    pub fn release(&mut self) -> bool {
+        let status = self.regs().s.read();
+        if status.ffl().bits() == 0 {
+            return false;
+        }
+        // Safety: The written index must be valid since it was retrieved from the
+        // peripheral, and the configuration was not changed.
+        unsafe {
+            self.regs().a.write(|w| w.fai().bits(status.fgi().bits()));
+        }
+        true
+    }
+
+    /// Receives messages for as long as they satisfy `pred`, passing each one
+    /// to `f`. Returns the number of messages received.
+    ///
+    /// A FIFO can only be acknowledged up to an element, so this stops at the
+    /// first message that does not satisfy `pred` and leaves it in the FIFO
+    /// together with all messages received after it, matching or not. This
+    /// allows handling frames of latency-critical IDs right away while
+    /// deferring the rest, e.g. to the main loop. Routing such frames to the
+    /// other FIFO with a filter makes them available regardless of the order.
+    pub fn drain_filter(
+        &mut self,
+        mut pred: impl FnMut(&M) -> bool,
+        mut f: impl FnMut(M),
+    ) -> usize {
+        let mut received = 0;
+        loop {
+            let status = self.regs().s.read();
+            if status.ffl().bits() == 0 {
+                return received;
+            }
+            let get_index = status.fgi().bits() as usize;
+            let message = self.memory[get_index].get();
+            if !pred(&message) {
+                return received;
+            }
+            // Safety: The written index must be valid since it was retrieved from the
+            // peripheral, and the configuration was not changed.
+            unsafe {
+                self.regs().a.write(|w| w.fai().bits(get_index as u8));
+            }
+            f(message);
+            received += 1;
+        }
+    }
+
+    /// Receives the oldest frame in the format kept by `format`, passing
+    /// frames of the other format that were received before it to
+    /// `discarded`. Returns `None` once the FIFO is empty.
+    ///
+    /// This is the software counterpart of a format filter, which the
+    /// peripheral lacks, see [`FrameFormatFilter`].
+    pub fn receive_format(
+        &mut self,
+        format: FrameFormatFilter,
+        mut discarded: impl FnMut(M),
+    ) -> Option<M> {
+        for message in self.by_ref() {
+            if format.accepts(&message) {
+                return Some(message);
+            }
+            discarded(message);
+        }
+        None
+    }
+
+    /// Raw view of the element at `index` of the FIFO storage as it is laid
+    /// out in the message RAM: two header words followed by the data words.
+    ///
+    /// This is an escape hatch for performance-critical code that does not
+    /// want to copy the element into a typed message. The element at the get
+    /// index (RXFS.FGI) is the oldest one; it still has to be acknowledged
+    /// through the registers after reading it.
+    ///
+    /// # Safety
+    /// The peripheral writes to the message RAM concurrently, which the
+    /// compiler does not know about. The returned slice must only be used
+    /// while the element is owned by the software, i.e. after it was
+    /// reported as filled and before it is acknowledged.
+    ///
+    /// # Panics
+    /// If `index` is not less than the capacity of the FIFO.
+    pub unsafe fn element_words(&self, index: usize) -> &[u32] {
+        let element = self.memory[index].as_ptr() as *const u32;
+        core::slice::from_raw_parts(element, core::mem::size_of::<M>() / 4)
+    }
 }
 
 impl<'a, F, P: mcan_core::CanId, M: rx::AnyMessage> DynRxFifo for RxFifo<'a, F, P, M>
@@ -107,7 +265,7 @@ where
     }
 
     fn capacity(&self) -> usize {
-        self.memory.len()
+        self.regs().c.read().fs().bits() as usize
     }
 
     fn receive(&mut self) -> nb::Result<Self::Message, Infallible> {
@@ -138,3 +296,47 @@ where
         self.receive().ok()
     }
 }
+
+/// RX FIFO with a handler for received messages, created by
+/// [`RxFifo::on_message`]
+///
+/// ```no_run
+/// # use mcan::interrupt::{state::EnabledLine0, Interrupt, OwnedInterruptSet};
+/// # use mcan::rx_fifo::{DynRxFifo, MessageHandler};
+/// // Called from the interrupt handler of line 0
+/// fn on_line_0<Id: mcan::core::CanId, F: DynRxFifo>(
+///     interrupts: &OwnedInterruptSet<Id, EnabledLine0>,
+///     fifo_0: &mut MessageHandler<F, impl FnMut(F::Message)>,
+/// ) {
+///     for interrupt in interrupts.iter_flagged() {
+///         if let Interrupt::RxFifo0NewMessage = interrupt {
+///             fifo_0.handle_interrupt();
+///         }
+///     }
+/// }
+/// ```
+pub struct MessageHandler<F, H> {
+    fifo: F,
+    handler: H,
+}
+
+impl<F: DynRxFifo, H: FnMut(F::Message)> MessageHandler<F, H> {
+    /// Passes all messages in the FIFO to the handler. Returns the number of
+    /// messages handled.
+    ///
+    /// The new message interrupt flag has to be cleared before calling this,
+    /// otherwise a message received in between could go unnoticed.
+    pub fn handle_interrupt(&mut self) -> usize {
+        let mut handled = 0;
+        while let Ok(message) = self.fifo.receive() {
+            (self.handler)(message);
+            handled += 1;
+        }
+        handled
+    }
+
+    /// Separates the FIFO and the handler again
+    pub fn release(self) -> (F, H) {
+        (self.fifo, self.handler)
+    }
+}
@@ -0,0 +1,216 @@
+//! Software ring buffer for received messages
+//!
+//! A common pattern is to move incoming messages out of an RX FIFO in an
+//! interrupt handler (e.g. on [`Interrupt::RxFifo0NewMessage`]) and consume
+//! them later from the main loop. [`RxRing`] is a single-producer,
+//! single-consumer queue for exactly that: the [`Producer`] half drains a
+//! FIFO in the interrupt context and the [`Consumer`] half pops messages
+//! elsewhere.
+//!
+//! ```no_run
+//! use mcan::message::rx;
+//! use mcan::rx_fifo::DynRxFifo;
+//! use mcan::rx_ring::{Consumer, Producer};
+//!
+//! type Message = rx::Message<8>;
+//!
+//! // Called on `Interrupt::RxFifo0NewMessage`
+//! fn on_interrupt(
+//!     producer: &mut Producer<'_, Message, 16>,
+//!     rx_fifo_0: &mut impl DynRxFifo<Message = Message>,
+//! ) {
+//!     producer.drain(rx_fifo_0);
+//! }
+//!
+//! // Called from the main loop
+//! fn poll(consumer: &mut Consumer<'_, Message, 16>) {
+//!     while let Some(message) = consumer.pop() {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! [`Interrupt::RxFifo0NewMessage`]: crate::interrupt::Interrupt::RxFifo0NewMessage
+
+use crate::rx_fifo::DynRxFifo;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Single-producer, single-consumer ring of `N` messages of type `M`
+pub struct RxRing<M, const N: usize> {
+    buffer: UnsafeCell<MaybeUninit<[M; N]>>,
+    /// Total number of messages pushed, wrapping
+    write: AtomicUsize,
+    /// Total number of messages popped, wrapping
+    read: AtomicUsize,
+}
+
+// Safety: Access to the buffer is synchronized through `write` and `read`, and
+// only a single `Producer` and a single `Consumer` exist at any time.
+unsafe impl<M: Send, const N: usize> Sync for RxRing<M, N> {}
+
+impl<M: Copy, const N: usize> RxRing<M, N> {
+    /// Create an empty ring
+    pub const fn new() -> Self {
+        Self {
+            buffer: UnsafeCell::new(MaybeUninit::uninit()),
+            write: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+        }
+    }
+
+    /// Splits the ring into its producer and consumer halves
+    pub fn split(&mut self) -> (Producer<'_, M, N>, Consumer<'_, M, N>) {
+        (Producer { ring: self }, Consumer { ring: self })
+    }
+
+    /// Returns the number of messages the ring can hold
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    fn len(&self) -> usize {
+        self.write
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.read.load(Ordering::Acquire))
+    }
+
+    fn slot(&self, index: usize) -> *mut M {
+        // Safety: `index % N` is within the bounds of the buffer
+        unsafe { (self.buffer.get() as *mut M).add(index % N) }
+    }
+}
+
+impl<M: Copy, const N: usize> Default for RxRing<M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Producing half of an [`RxRing`]
+pub struct Producer<'a, M, const N: usize> {
+    ring: &'a RxRing<M, N>,
+}
+
+impl<'a, M: Copy, const N: usize> Producer<'a, M, N> {
+    /// Returns `true` if no more messages can be pushed
+    pub fn is_full(&self) -> bool {
+        self.ring.len() >= N
+    }
+
+    /// Appends a message to the ring. Returns back the `message` if the ring
+    /// is full.
+    pub fn push(&mut self, message: M) -> Result<(), M> {
+        if self.is_full() {
+            return Err(message);
+        }
+        let write = self.ring.write.load(Ordering::Relaxed);
+        // Safety: The slot is not visible to the consumer until `write` is
+        // advanced.
+        unsafe { self.ring.slot(write).write(message) };
+        self.ring
+            .write
+            .store(write.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Moves messages from `fifo` into the ring until either the FIFO is empty
+    /// or the ring is full. Returns the number of messages moved.
+    ///
+    /// Messages that do not fit remain in the FIFO.
+    pub fn drain<F: DynRxFifo<Message = M>>(&mut self, fifo: &mut F) -> usize {
+        let mut moved = 0;
+        while !self.is_full() {
+            match fifo.receive() {
+                Ok(message) => {
+                    // Cannot fail, the ring was checked not to be full
+                    let _ = self.push(message);
+                    moved += 1;
+                }
+                Err(_) => break,
+            }
+        }
+        moved
+    }
+}
+
+/// Consuming half of an [`RxRing`]
+pub struct Consumer<'a, M, const N: usize> {
+    ring: &'a RxRing<M, N>,
+}
+
+impl<'a, M: Copy, const N: usize> Consumer<'a, M, N> {
+    /// Returns the number of messages in the ring
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Returns `true` if the ring is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes the oldest message from the ring
+    pub fn pop(&mut self) -> Option<M> {
+        if self.is_empty() {
+            return None;
+        }
+        let read = self.ring.read.load(Ordering::Relaxed);
+        // Safety: The slot was initialized by the producer before `write` was
+        // advanced, and it is not reused until `read` is advanced.
+        let message = unsafe { self.ring.slot(read).read() };
+        self.ring
+            .read
+            .store(read.wrapping_add(1), Ordering::Release);
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::convert::Infallible;
+
+    struct FakeFifo(core::ops::Range<u32>);
+
+    impl DynRxFifo for FakeFifo {
+        type RxFifoId = ();
+        type CanId = ();
+        type Message = u32;
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn is_empty(&self) -> bool {
+            self.0.is_empty()
+        }
+
+        fn capacity(&self) -> usize {
+            64
+        }
+
+        fn receive(&mut self) -> nb::Result<u32, Infallible> {
+            self.0.next().ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    #[test]
+    fn drain_stops_when_full() {
+        let mut ring = RxRing::<u32, 4>::new();
+        let (mut producer, mut consumer) = ring.split();
+        let mut fifo = FakeFifo(0..6);
+        assert_eq!(producer.drain(&mut fifo), 4);
+        assert!(producer.is_full());
+        assert_eq!(fifo.len(), 2);
+        assert_eq!(consumer.pop(), Some(0));
+        assert_eq!(producer.drain(&mut fifo), 1);
+        for i in 1..=4 {
+            assert_eq!(consumer.pop(), Some(i));
+        }
+        assert_eq!(producer.drain(&mut fifo), 1);
+        assert_eq!(consumer.pop(), Some(5));
+        assert!(consumer.is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Absolute time for timestamps of frames
+//!
+//! The 16-bit timestamps captured into received frames and TX events only
+//! count ticks of the timestamp counter. With the counter running from an
+//! external source of known frequency, see [`TimeStampSelect::EXT`],
+//! [`TimestampSync`] relates these ticks to UTC by recording the counter
+//! value at the edges of a pulse-per-second (PPS) signal.
+//!
+//! ```no_run
+//! use mcan::bus::DynAux;
+//! use mcan::timestamp::TimestampSync;
+//!
+//! // Called on the PPS edge marking the start of UTC second `utc_seconds`
+//! fn on_pps(sync: &mut TimestampSync, aux: &impl DynAux, utc_seconds: u64) {
+//!     sync.on_pps(aux.timestamp(), utc_seconds);
+//! }
+//!
+//! fn log(sync: &TimestampSync, message: &impl mcan::message::rx::AnyMessage) {
+//!     if let Some(utc_nanos) = sync.to_utc_nanos(message.timestamp()) {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! [`TimeStampSelect::EXT`]: crate::config::TimeStampSelect::EXT
+
+use fugit::HertzU32;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// The counter frequency passed to [`TimestampSync::new`] is zero
+#[derive(Debug)]
+pub struct ZeroFrequencyError;
+
+/// Converts timestamps to UTC based on the counter value captured at the
+/// last PPS edge
+#[derive(Copy, Clone, Debug)]
+pub struct TimestampSync {
+    counter_frequency: HertzU32,
+    /// Counter value and UTC second of the last PPS edge
+    pps: Option<(u16, u64)>,
+}
+
+impl TimestampSync {
+    /// Creates a helper for a timestamp counter that is incremented at
+    /// `counter_frequency`. No timestamps are converted before the first
+    /// call to [`Self::on_pps`].
+    pub fn new(counter_frequency: HertzU32) -> Result<Self, ZeroFrequencyError> {
+        if counter_frequency.to_Hz() == 0 {
+            return Err(ZeroFrequencyError);
+        }
+        Ok(Self {
+            counter_frequency,
+            pps: None,
+        })
+    }
+
+    /// Records the timestamp `counter` value at the PPS edge that marks the
+    /// start of the UTC second `utc_seconds`.
+    ///
+    /// This is the capture hook, to be called on every PPS edge with the
+    /// value of [`DynAux::timestamp`] read as close to the edge as possible,
+    /// e.g. in the PPS interrupt handler.
+    ///
+    /// [`DynAux::timestamp`]: crate::bus::DynAux::timestamp
+    pub fn on_pps(&mut self, counter: u16, utc_seconds: u64) {
+        self.pps = Some((counter, utc_seconds));
+    }
+
+    /// Converts the timestamp `raw_ts` of a frame to nanoseconds since the
+    /// Unix epoch. Returns `None` if no PPS edge was recorded yet, or if the
+    /// result does not fit a `u64`.
+    ///
+    /// The 16-bit counter wraps around, so the frame has to be timestamped
+    /// within half a wraparound period before or after the last PPS edge.
+    /// Timestamps before it are converted as well, so frames can be
+    /// converted after the next edge was recorded.
+    pub fn to_utc_nanos(&self, raw_ts: u16) -> Option<u64> {
+        let (counter, utc_seconds) = self.pps?;
+        let ticks = i64::from(raw_ts.wrapping_sub(counter) as i16);
+        let offset = ticks * NANOS_PER_SECOND as i64 / i64::from(self.counter_frequency.to_Hz());
+        utc_seconds
+            .checked_mul(NANOS_PER_SECOND)?
+            .checked_add_signed(offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fugit::RateExtU32 as _;
+
+    #[test]
+    fn converts_relative_to_last_pps() {
+        let mut sync = TimestampSync::new(1.MHz()).unwrap();
+        assert_eq!(sync.to_utc_nanos(0), None);
+        sync.on_pps(0xfff0, 1_700_000_000);
+        let pps = 1_700_000_000 * NANOS_PER_SECOND;
+        assert_eq!(sync.to_utc_nanos(0xfff0), Some(pps));
+        // Across the wraparound of the counter
+        assert_eq!(sync.to_utc_nanos(0x0010), Some(pps + 32_000));
+        // Before the edge
+        assert_eq!(sync.to_utc_nanos(0xffe0), Some(pps - 16_000));
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        assert!(TimestampSync::new(0.Hz()).is_err());
+        let mut sync = TimestampSync::new(1.MHz()).unwrap();
+        sync.on_pps(0, u64::MAX);
+        assert_eq!(sync.to_utc_nanos(0), None);
+        // Before the epoch
+        sync.on_pps(0x10, 0);
+        assert_eq!(sync.to_utc_nanos(0), None);
+    }
+}
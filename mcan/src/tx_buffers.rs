@@ -7,6 +7,7 @@
 //! queue is configurable; see [`crate::config::TxQueueMode`].
 
 use crate::config::Mode;
+use crate::message::tx;
 use crate::messageram::Capacities;
 use crate::reg;
 use core::convert::Infallible;
@@ -18,7 +19,8 @@ use vcell::VolatileCell;
 /// Tx specific errors
 #[derive(Debug)]
 pub enum Error {
-    /// Index is out of bounds
+    /// Index is out of bounds, or the queue was used without any buffers
+    /// assigned to it
     OutOfBounds,
     /// Support for sending CAN FD messages is disabled
     ///
@@ -30,6 +32,32 @@ pub enum Error {
     /// In order to be able to send CAN FD messages change its mode of operation
     /// to [`Mode::Fd { bit_rate_switching: true }`].
     BitRateSwitchingDisabled,
+    /// The frame was not transmitted successfully within the allowed number
+    /// of attempts
+    RetriesExhausted,
+    /// The peripheral is in bus monitoring or restricted operation mode, in
+    /// which it does not transmit frames
+    TxNotAllowed,
+    /// The transmit queue operates in [`TxQueueMode::Priority`], which does not
+    /// preserve the order of frames
+    ///
+    /// [`TxQueueMode::Priority`]: crate::config::TxQueueMode::Priority
+    QueueNotFifo,
+    /// The payload does not fit the frame format or the TX buffer elements
+    TooMuchData,
+    /// The frame was not transmitted within the allowed number of polls, see
+    /// [`DynTx::transmit_blocking`] and [`DynTx::transmit_with_retries`]
+    Timeout,
+    /// The peripheral entered the bus off state while waiting for the
+    /// transmission, see [`DynTx::transmit_blocking`] and
+    /// [`DynTx::transmit_with_retries`]
+    BusOff,
+    /// The queue was full when retrying a failed transmission, see
+    /// [`DynTx::transmit_with_retries`]
+    RetryBlocked {
+        /// Number of attempts made before
+        attempts: u8,
+    },
 }
 
 /// Transmit queue and dedicated buffers
@@ -54,9 +82,139 @@ pub trait DynTx {
         -> nb::Result<(), Error>;
 
     /// Puts a frame in the queue to be sent on the bus.
-    /// Fails with [`nb::Error::WouldBlock`] if the transmit buffer is full.
+    /// Fails with [`nb::Error::WouldBlock`] if the transmit buffer is full,
+    /// and with [`Error::OutOfBounds`] if all buffers are dedicated ones.
     fn transmit_queued(&mut self, message: Self::Message) -> nb::Result<(), Error>;
 
+    /// Puts as many of `messages` in the queue as there is room for, and
+    /// requests their transmission with a single register write. Returns the
+    /// number of messages queued, which are the leading ones of `messages`.
+    ///
+    /// Fails with [`nb::Error::WouldBlock`] if the queue is full and with
+    /// [`Error::OutOfBounds`] if there is no queue. If any of
+    /// the messages that would be queued cannot be sent in the current mode
+    /// of operation, nothing is queued.
+    ///
+    /// To refill the queue only once it has drained completely, call this on
+    /// [`Interrupt::TxFifoEmpty`].
+    ///
+    /// [`Interrupt::TxFifoEmpty`]: crate::interrupt::Interrupt::TxFifoEmpty
+    fn transmit_batch(&mut self, messages: &[Self::Message]) -> nb::Result<usize, Error>;
+
+    /// Like [`Self::transmit_batch`], but guarantees that the frames appear on
+    /// the bus in the order of `messages`, and after any frames queued
+    /// earlier.
+    ///
+    /// This relies on the transmit queue operating in [`TxQueueMode::Fifo`],
+    /// where the queue is sent in the order frames are put into it, and
+    /// fails with [`Error::QueueNotFifo`] otherwise. Frames that do not fit
+    /// can be passed to a later call without breaking the order. Frames in
+    /// dedicated transmit buffers are still sent according to their
+    /// priority, so they may be sent in between.
+    ///
+    /// [`TxQueueMode::Fifo`]: crate::config::TxQueueMode::Fifo
+    fn transmit_ordered(&mut self, messages: &[Self::Message]) -> nb::Result<usize, Error>;
+
+    /// Puts frames from `frames` in the queue until either the queue is full
+    /// or the iterator is exhausted. Returns the number of frames queued.
+    ///
+    /// This never waits for the queue to drain; call it again when buffers
+    /// become available, e.g. on [`Interrupt::TransmissionCompleted`]. Frames
+    /// that do not fit remain in `frames`. Fails with
+    /// [`nb::Error::WouldBlock`] if the queue is full and frames are pending.
+    /// If a frame cannot be sent in the current mode of operation, it is
+    /// consumed and the error is returned.
+    ///
+    /// [`Interrupt::TransmissionCompleted`]: crate::interrupt::Interrupt::TransmissionCompleted
+    fn transmit_stream<I: Iterator<Item = Self::Message>>(
+        &mut self,
+        frames: &mut core::iter::Peekable<I>,
+    ) -> nb::Result<usize, Error>
+    where
+        Self::Message: Copy,
+    {
+        let mut queued = 0;
+        while let Some(&frame) = frames.peek() {
+            match self.transmit_queued(frame) {
+                Ok(()) => {
+                    frames.next();
+                    queued += 1;
+                }
+                Err(nb::Error::WouldBlock) if queued > 0 => break,
+                Err(e) => {
+                    if let nb::Error::Other(_) = e {
+                        frames.next();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(queued)
+    }
+
+    /// Puts a frame in the queue with a request to store a TX event tagged
+    /// with `marker` on transmission, replacing any marker already set.
+    ///
+    /// Together with [`DynTxEventFifo::find`], this allows looking up when a
+    /// specific frame went on the bus, e.g. to measure its latency.
+    ///
+    /// [`DynTxEventFifo::find`]: crate::tx_event_fifo::DynTxEventFifo::find
+    fn transmit_traced(&mut self, message: Self::Message, marker: u8) -> nb::Result<(), Error>
+    where
+        Self::Message: tx::AnyMessage,
+    {
+        self.transmit_queued(tx::AnyMessage::with_tx_event(message, Some(marker)))
+    }
+
+    /// Puts a frame in the queue and blocks until it was transmitted, making up
+    /// to `max_retries` further attempts if a transmission fails. Returns the
+    /// number of attempts made.
+    ///
+    /// This is intended to be used with automatic retransmission disabled
+    /// ([`TxConfig::disable_automatic_retransmission`]), where the peripheral
+    /// gives up after a lost arbitration or an error and reports the
+    /// transmission as cancelled. With automatic retransmission enabled, the
+    /// peripheral retries on its own and a single attempt lasts until the
+    /// frame is sent.
+    ///
+    /// Each attempt polls the peripheral at most `timeout_spins` times. This
+    /// bounds the wait if the frame is never acknowledged, e.g. without a
+    /// transceiver or other nodes on the bus.
+    ///
+    /// Fails with [`nb::Error::WouldBlock`] if the queue is full, in which
+    /// case no attempt was made, and with [`Error::RetriesExhausted`] if all
+    /// attempts failed. If the queue is full or the buffer at the put index
+    /// is still in use when retrying, which only happens if other frames were
+    /// queued in between, it fails with [`Error::RetryBlocked`] instead,
+    /// reporting the attempts made. Fails
+    /// with [`Error::Timeout`] and [`Error::BusOff`] like
+    /// [`Self::transmit_blocking`], and with [`Error::OutOfBounds`] if all
+    /// buffers are dedicated ones.
+    ///
+    /// [`TxConfig::disable_automatic_retransmission`]: crate::config::TxConfig::disable_automatic_retransmission
+    fn transmit_with_retries(
+        &mut self,
+        message: Self::Message,
+        max_retries: u8,
+        timeout_spins: u32,
+    ) -> nb::Result<u8, Error>;
+
+    /// Puts a frame in the queue and blocks until it was transmitted, polling
+    /// the peripheral at most `timeout_spins` times.
+    ///
+    /// Fails with [`nb::Error::WouldBlock`] if the queue is full, in which
+    /// case nothing was queued. Fails with [`Error::Timeout`] if the frame was
+    /// not transmitted in time, in which case its cancellation is requested,
+    /// and with [`Error::BusOff`] if the peripheral went bus off, in which
+    /// case the frame remains pending until the bus is recovered. A frame
+    /// cancelled by the peripheral, with automatic retransmission disabled,
+    /// is reported as [`Error::RetriesExhausted`].
+    fn transmit_blocking(
+        &mut self,
+        message: Self::Message,
+        timeout_spins: u32,
+    ) -> nb::Result<(), Error>;
+
     /// Allow [`Interrupt::TransmissionCancellationFinished`] to be triggered by
     /// `to_be_enabled`. Interrupts for other buffers remain unchanged.
     ///
@@ -87,6 +245,26 @@ pub trait DynTx {
     /// [`Interrupt::TransmissionCompleted`]: crate::interrupt::Interrupt::TransmissionCompleted
     fn disable_transmission_completed_interrupt(&mut self, to_be_disabled: TxBufferSet);
 
+    /// Allow or disallow [`Interrupt::TransmissionCompleted`] to be triggered
+    /// by the transmit buffer at `index`. Interrupts for other buffers remain
+    /// unchanged.
+    ///
+    /// Fails with [`Error::OutOfBounds`] if `index` does not refer to a
+    /// configured transmit buffer.
+    ///
+    /// [`Interrupt::TransmissionCompleted`]: crate::interrupt::Interrupt::TransmissionCompleted
+    fn set_transmit_interrupt(&mut self, index: usize, on: bool) -> Result<(), Error>;
+
+    /// Allow or disallow [`Interrupt::TransmissionCancellationFinished`] to be
+    /// triggered by the transmit buffer at `index`. Interrupts for other
+    /// buffers remain unchanged.
+    ///
+    /// Fails with [`Error::OutOfBounds`] if `index` does not refer to a
+    /// configured transmit buffer.
+    ///
+    /// [`Interrupt::TransmissionCancellationFinished`]: crate::interrupt::Interrupt::TransmissionCancellationFinished
+    fn set_cancel_interrupt(&mut self, index: usize, on: bool) -> Result<(), Error>;
+
     /// Returns the set of `TxBuffer`s that the peripheral indicates have been
     /// cancelled. The flags are only cleared when a new transmission is
     /// requested for the buffer.
@@ -97,6 +275,21 @@ pub trait DynTx {
     /// transmission is requested for the buffer.
     fn get_transmission_completed_flags(&self) -> TxBufferSet;
 
+    /// Returns the set of `TxBuffer`s with a transmission request pending
+    /// (TXBRP), i.e. the buffers that still hold frames waiting to be sent.
+    fn pending_mask(&self) -> TxBufferSet;
+
+    /// Returns the state of the transmit buffer at `index`, combining the
+    /// pending (TXBRP), transmission occurred (TXBTO) and cancellation
+    /// finished (TXBCF) flags.
+    ///
+    /// The peripheral does not report arbitration loss per frame; a frame that
+    /// lost arbitration is retransmitted and stays [`TxStatus::Pending`].
+    ///
+    /// Fails with [`Error::OutOfBounds`] if `index` does not refer to a
+    /// configured transmit buffer.
+    fn status(&self, index: usize) -> Result<TxStatus, Error>;
+
     /// Returns an iterator over the set of `TxBuffer`s that the peripheral
     /// indicates have been cancelled. The flags are only cleared when a new
     /// transmission is requested for the buffer.
@@ -145,6 +338,93 @@ impl<'a, P: mcan_core::CanId, C: Capacities> Tx<'a, P, C> {
         }
     }
 
+    /// Raw mutable view of the TX buffer element at `index` as it is laid
+    /// out in the message RAM: two header words followed by the data words.
+    ///
+    /// This is the transmit counterpart of [`RxFifo::element_words`]. After
+    /// filling in the element, its transmission is requested with
+    /// [`Self::request_transmission`].
+    ///
+    /// # Safety
+    /// The element must not be pending transmission, since the peripheral may
+    /// read it at any time. Neither the header nor the data is validated; it
+    /// must describe a frame that the current mode of operation is able to
+    /// send.
+    ///
+    /// # Panics
+    /// If `index` is not less than the number of TX buffers.
+    ///
+    /// [`RxFifo::element_words`]: crate::rx_fifo::RxFifo::element_words
+    pub unsafe fn element_words_mut(&mut self, index: usize) -> &mut [u32] {
+        let element = self.memory[index].as_ptr() as *mut u32;
+        core::slice::from_raw_parts_mut(element, core::mem::size_of::<C::TxMessage>() / 4)
+    }
+
+    /// Requests the transmission of the TX buffer element at `index` as it is
+    /// currently stored in the message RAM.
+    ///
+    /// Fails with [`Error::OutOfBounds`] if `index` does not refer to a TX
+    /// buffer.
+    ///
+    /// # Safety
+    /// The element must have been filled with a valid frame, e.g. using
+    /// [`Self::element_words_mut`].
+    pub unsafe fn request_transmission(&mut self, index: usize) -> Result<(), Error> {
+        self.buffer_set(index)?;
+        self.add_request(index);
+        Ok(())
+    }
+
+    /// Puts the frame described by `message` in the queue to be sent on the
+    /// bus, copying its payload straight into the message RAM.
+    ///
+    /// Unlike building a [`C::TxMessage`] and passing it to
+    /// [`DynTx::transmit_queued`], this does not copy the payload into an
+    /// intermediate element first, which matters for large CAN FD payloads.
+    /// The element is written with word accesses.
+    ///
+    /// Fails with [`Error::TooMuchData`] if the payload does not fit the
+    /// frame format or the TX buffer elements, and with
+    /// [`nb::Error::WouldBlock`] if the queue is full.
+    ///
+    /// [`C::TxMessage`]: crate::messageram::Capacities::TxMessage
+    pub fn transmit_from_slice(&mut self, message: tx::MessageBuilder) -> nb::Result<(), Error> {
+        self.check_queue()?;
+        let (header, payload) = message.encode().map_err(|_| Error::TooMuchData)?;
+        let data_words = core::mem::size_of::<C::TxMessage>() / 4 - header.len();
+        if payload.len() > data_words * 4 {
+            return Err(nb::Error::Other(Error::TooMuchData));
+        }
+        let fd_format = header[1] & (1 << 21) != 0;
+        let bit_rate_switching = header[1] & (1 << 20) != 0;
+        self.validate_format(fd_format, bit_rate_switching)?;
+        self.check_operation_mode()?;
+        let index = self.find_put_index().ok_or(nb::Error::WouldBlock)?;
+        if self.is_buffer_in_use(index) {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // The payload is followed by zeros, which pad CAN FD frames to the
+        // length encoded in the header
+        let mut chunks = payload.chunks(4);
+        let data = (0..data_words).map(|_| {
+            let mut word = [0; 4];
+            if let Some(chunk) = chunks.next() {
+                word[..chunk.len()].copy_from_slice(chunk);
+            }
+            u32::from_ne_bytes(word)
+        });
+        let element = self.memory.get_mut(index).ok_or(Error::OutOfBounds)?;
+        let element = element.as_ptr() as *mut u32;
+        for (offset, word) in header.into_iter().chain(data).enumerate() {
+            // Safety: The element is not pending transmission and consists of
+            // the two header words followed by `data_words` data words.
+            unsafe { element.add(offset).write_volatile(word) };
+        }
+        self.add_request(index);
+        Ok(())
+    }
+
     /// Raw access to the registers.
     unsafe fn regs(&self) -> &reg::RegisterBlock {
         &(*P::register_block())
@@ -155,6 +435,11 @@ impl<'a, P: mcan_core::CanId, C: Capacities> Tx<'a, P, C> {
         unsafe { &self.regs().txfqs }
     }
 
+    fn txbc(&self) -> &reg::TXBC {
+        // Safety: TXBC is only read, its queue mode is set up by `CanConfigurable`.
+        unsafe { &self.regs().txbc }
+    }
+
     fn txbrp(&self) -> &reg::TXBRP {
         // Safety: `Self` owns the register.
         unsafe { &self.regs().txbrp }
@@ -212,6 +497,7 @@ impl<'a, P: mcan_core::CanId, C: Capacities> Tx<'a, P, C> {
         if self.is_buffer_in_use(index) {
             return Err(nb::Error::WouldBlock);
         }
+        self.check_operation_mode()?;
         self.validate_message(&message)?;
         self.memory
             .get_mut(index)
@@ -221,6 +507,16 @@ impl<'a, P: mcan_core::CanId, C: Capacities> Tx<'a, P, C> {
         Ok(())
     }
 
+    /// Fails if all buffers are dedicated ones. The put index in TXFQS has no
+    /// meaning without a queue and must not be used then.
+    fn check_queue(&self) -> Result<(), Error> {
+        if C::TxBuffers::USIZE > C::DedicatedTxBuffers::USIZE {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds)
+        }
+    }
+
     /// Returns the put index if available. `None` if the queue is full.
     fn find_put_index(&self) -> Option<usize> {
         let status = self.txfqs().read();
@@ -240,12 +536,65 @@ impl<'a, P: mcan_core::CanId, C: Capacities> Tx<'a, P, C> {
         }
     }
 
+    /// Polls the flags of the buffer at `index` after its transmission was
+    /// requested, at most `timeout_spins` times. Returns `true` if the frame
+    /// was transmitted and `false` if the peripheral cancelled it. On timeout,
+    /// the cancellation of the frame is requested.
+    fn wait_for_transmission(&self, index: usize, timeout_spins: u32) -> Result<bool, Error> {
+        // Requesting the transmission resets both flags of the buffer
+        let mask = 1 << index;
+        for _ in 0..timeout_spins {
+            if self.txbto().read().bits() & mask != 0 {
+                return Ok(true);
+            }
+            if self.txbcf().read().bits() & mask != 0 {
+                return Ok(false);
+            }
+            // Entering bus off sets INIT. PSR is not read, as that would reset
+            // the last error codes tracked by `Aux`.
+            //
+            // Safety: CCCR is only read.
+            if unsafe { self.regs() }.cccr.read().init().bit_is_set() {
+                return Err(Error::BusOff);
+            }
+            core::hint::spin_loop();
+        }
+        // Safety: There are no reserved bit patterns.
+        unsafe { self.txbcr().write(|w| w.bits(mask)) };
+        Err(Error::Timeout)
+    }
+
+    /// Returns the set containing only the buffer at `index`, if it exists.
+    fn buffer_set(&self, index: usize) -> Result<TxBufferSet, Error> {
+        if index < C::TxBuffers::USIZE {
+            Ok([index].into_iter().collect())
+        } else {
+            Err(Error::OutOfBounds)
+        }
+    }
+
+    /// Transmission requests are silently ignored in bus monitoring and
+    /// restricted operation mode.
+    fn check_operation_mode(&self) -> Result<(), Error> {
+        // Safety: CCCR is only read.
+        let cccr = unsafe { self.regs() }.cccr.read();
+        if cccr.mon().bit_is_set() || cccr.asm().bit_is_set() {
+            Err(Error::TxNotAllowed)
+        } else {
+            Ok(())
+        }
+    }
+
     fn validate_message(&self, message: &C::TxMessage) -> Result<(), Error> {
         use crate::message::Raw;
-        if message.fd_format() && !matches!(self.mode, Mode::Fd { .. }) {
+        self.validate_format(message.fd_format(), message.bit_rate_switching())
+    }
+
+    fn validate_format(&self, fd_format: bool, bit_rate_switching: bool) -> Result<(), Error> {
+        if fd_format && !matches!(self.mode, Mode::Fd { .. }) {
             return Err(Error::FdDisabled);
         }
-        if message.bit_rate_switching()
+        if bit_rate_switching
             && !matches!(
                 self.mode,
                 Mode::Fd {
@@ -269,17 +618,104 @@ impl<'a, P: mcan_core::CanId, C: Capacities> DynTx for Tx<'a, P, C> {
         index: usize,
         message: Self::Message,
     ) -> nb::Result<(), Error> {
-        if index > C::DedicatedTxBuffers::USIZE {
+        if index >= C::DedicatedTxBuffers::USIZE {
             Err(Error::OutOfBounds)?;
         }
         self.transmit(index, message)
     }
 
     fn transmit_queued(&mut self, message: Self::Message) -> nb::Result<(), Error> {
+        self.check_queue()?;
         let index = self.find_put_index().ok_or(nb::Error::WouldBlock)?;
         self.transmit(index, message)
     }
 
+    fn transmit_batch(&mut self, messages: &[Self::Message]) -> nb::Result<usize, Error> {
+        self.check_queue()?;
+        let status = self.txfqs().read();
+        let free = status.tffl().bits() as usize;
+        if free == 0 && !messages.is_empty() {
+            return Err(nb::Error::WouldBlock);
+        }
+        let messages = &messages[..messages.len().min(free)];
+        self.check_operation_mode()?;
+        for message in messages {
+            self.validate_message(message)?;
+        }
+
+        // Free queue buffers, starting at the put index. In FIFO mode, these
+        // are consecutive, preserving the order of transmission.
+        let first = C::DedicatedTxBuffers::USIZE;
+        let queue_len = C::TxBuffers::USIZE - first;
+        let put_index = status.tfqpi().bits() as usize;
+        let pending = self.txbar().read().bits() | self.txbrp().read().bits();
+        let free_buffers = (0..queue_len)
+            .map(|offset| first + (put_index - first + offset) % queue_len)
+            .filter(|index| pending & (1 << index) == 0);
+
+        let mut add_requests = 0;
+        for (index, message) in free_buffers.zip(messages) {
+            self.memory[index].set(*message);
+            add_requests |= 1 << index;
+        }
+        // Safety: There are no reserved bit patterns. Only free buffers are added.
+        unsafe { self.txbar().write(|w| w.bits(add_requests)) };
+        Ok(messages.len())
+    }
+
+    fn transmit_ordered(&mut self, messages: &[Self::Message]) -> nb::Result<usize, Error> {
+        // TFQM is set in priority mode, see `TxQueueMode`
+        if self.txbc().read().tfqm().bit_is_set() {
+            return Err(nb::Error::Other(Error::QueueNotFifo));
+        }
+        self.transmit_batch(messages)
+    }
+
+    fn transmit_with_retries(
+        &mut self,
+        message: Self::Message,
+        max_retries: u8,
+        timeout_spins: u32,
+    ) -> nb::Result<u8, Error> {
+        self.check_queue()?;
+        self.validate_message(&message)?;
+        for attempt in 1..=max_retries.saturating_add(1) {
+            // After a failed attempt, at least the cancelled buffer is free again
+            let queued = match self.find_put_index() {
+                Some(index) => self.transmit(index, message).map(|()| index),
+                None => Err(nb::Error::WouldBlock),
+            };
+            let index = match queued {
+                Ok(index) => index,
+                Err(nb::Error::WouldBlock) if attempt > 1 => {
+                    return Err(nb::Error::Other(Error::RetryBlocked {
+                        attempts: attempt - 1,
+                    }))
+                }
+                Err(e) => return Err(e),
+            };
+            if self.wait_for_transmission(index, timeout_spins)? {
+                return Ok(attempt);
+            }
+        }
+        Err(nb::Error::Other(Error::RetriesExhausted))
+    }
+
+    fn transmit_blocking(
+        &mut self,
+        message: Self::Message,
+        timeout_spins: u32,
+    ) -> nb::Result<(), Error> {
+        self.check_queue()?;
+        let index = self.find_put_index().ok_or(nb::Error::WouldBlock)?;
+        self.transmit(index, message)?;
+        if self.wait_for_transmission(index, timeout_spins)? {
+            Ok(())
+        } else {
+            Err(nb::Error::Other(Error::RetriesExhausted))
+        }
+    }
+
     fn enable_cancellation_interrupt(&mut self, to_be_enabled: TxBufferSet) {
         // Safety: There are no reserved bit patterns.
         unsafe {
@@ -312,6 +748,26 @@ impl<'a, P: mcan_core::CanId, C: Capacities> DynTx for Tx<'a, P, C> {
         }
     }
 
+    fn set_transmit_interrupt(&mut self, index: usize, on: bool) -> Result<(), Error> {
+        let buffer = self.buffer_set(index)?;
+        if on {
+            self.enable_transmission_completed_interrupt(buffer);
+        } else {
+            self.disable_transmission_completed_interrupt(buffer);
+        }
+        Ok(())
+    }
+
+    fn set_cancel_interrupt(&mut self, index: usize, on: bool) -> Result<(), Error> {
+        let buffer = self.buffer_set(index)?;
+        if on {
+            self.enable_cancellation_interrupt(buffer);
+        } else {
+            self.disable_cancellation_interrupt(buffer);
+        }
+        Ok(())
+    }
+
     fn get_cancellation_flags(&self) -> TxBufferSet {
         TxBufferSet(self.txbcf().read().bits())
     }
@@ -320,6 +776,25 @@ impl<'a, P: mcan_core::CanId, C: Capacities> DynTx for Tx<'a, P, C> {
         TxBufferSet(self.txbto().read().bits())
     }
 
+    fn pending_mask(&self) -> TxBufferSet {
+        TxBufferSet(self.txbrp().read().bits())
+    }
+
+    fn status(&self, index: usize) -> Result<TxStatus, Error> {
+        let buffer = self.buffer_set(index)?.0;
+        Ok(if self.txbrp().read().bits() & buffer != 0 {
+            TxStatus::Pending
+        } else if self.txbto().read().bits() & buffer != 0 {
+            // A cancellation requested too late sets TXBCF as well, but the
+            // frame was transmitted nonetheless.
+            TxStatus::Completed
+        } else if self.txbcf().read().bits() & buffer != 0 {
+            TxStatus::Cancelled
+        } else {
+            TxStatus::Idle
+        })
+    }
+
     fn iter_cancellation_flags(&self) -> Iter {
         self.get_cancellation_flags().iter()
     }
@@ -343,6 +818,21 @@ impl<'a, P: mcan_core::CanId, C: Capacities> DynTx for Tx<'a, P, C> {
     }
 }
 
+/// State of a single transmit buffer, see [`DynTx::status`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TxStatus {
+    /// No transmission has been requested since the peripheral was configured
+    Idle,
+    /// A transmission is requested and has not finished yet, e.g. because the
+    /// frame is waiting for the bus or lost arbitration
+    Pending,
+    /// The most recently requested frame was transmitted successfully
+    Completed,
+    /// The most recently requested frame was cancelled before it was
+    /// transmitted
+    Cancelled,
+}
+
 /// A set of transmit buffers, which may be dedicated buffers or part of the
 /// queue.
 #[derive(Copy, Clone)]
@@ -395,3 +885,261 @@ impl Iterator for Iter {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message::{rx, tx::MessageBuilder};
+    use crate::messageram::SharedMemory;
+    use embedded_can::StandardId;
+    use generic_array::typenum::consts::*;
+
+    /// Two dedicated buffers followed by a queue of four
+    struct Queue;
+    impl Capacities for Queue {
+        type StandardFilters = U0;
+        type ExtendedFilters = U0;
+        type RxBufferMessage = rx::Message<8>;
+        type DedicatedRxBuffers = U0;
+        type RxFifo0Message = rx::Message<8>;
+        type RxFifo0 = U0;
+        type RxFifo1Message = rx::Message<8>;
+        type RxFifo1 = U0;
+        type TxMessage = tx::Message<8>;
+        type TxBuffers = U6;
+        type DedicatedTxBuffers = U2;
+        type TxEventFifo = U0;
+    }
+
+    const REGISTER_WORDS: usize = core::mem::size_of::<reg::RegisterBlock>() / 4;
+
+    /// Declares the peripheral `$id` with a register block in plain memory,
+    /// one per test as the tests run in parallel. The registers keep what is
+    /// written to them, so flags are preset with [`preset`] instead.
+    macro_rules! fake_peripheral {
+        ($id:ident) => {
+            static mut REGISTERS: [u32; REGISTER_WORDS] = [0; REGISTER_WORDS];
+            struct $id;
+            // Safety: The register block is only used by this test.
+            unsafe impl mcan_core::CanId for $id {
+                const ADDRESS: *const () = &raw const REGISTERS as *const ();
+            }
+        };
+    }
+
+    fn registers<P: mcan_core::CanId>() -> &'static reg::RegisterBlock {
+        // Safety: See `fake_peripheral`.
+        unsafe { &*P::register_block() }
+    }
+
+    /// Sets a register, regardless of whether software may write it
+    fn preset(register: *mut u32, value: u32) {
+        // Safety: The register is plain memory, see `fake_peripheral`.
+        unsafe { register.write_volatile(value) }
+    }
+
+    /// Presets the free level and the put index of the queue in TXFQS
+    fn preset_queue<P: mcan_core::CanId>(free: u32, put_index: u32) {
+        let full = if free == 0 { 1 << 21 } else { 0 };
+        preset(
+            registers::<P>().txfqs.as_ptr(),
+            free | put_index << 16 | full,
+        );
+    }
+
+    fn tx<P: mcan_core::CanId>(memory: &mut SharedMemory<Queue>) -> Tx<'_, P, Queue> {
+        // Safety: The test owns the fake peripheral.
+        unsafe { Tx::new(&mut memory.init().tx_buffers, Mode::Classic) }
+    }
+
+    fn classic(id: u16) -> tx::Message<8> {
+        MessageBuilder {
+            id: StandardId::new(id).unwrap().into(),
+            frame_type: tx::FrameType::Classic(tx::ClassicFrameType::Data(&[])),
+            store_tx_event: None,
+        }
+        .build()
+        .unwrap()
+    }
+
+    /// Standard ID of the frame in the buffer at `index`, 0 if unused
+    fn queued_id<P: mcan_core::CanId>(tx: &mut Tx<'_, P, Queue>, index: usize) -> u32 {
+        // Safety: The element is only read.
+        unsafe { tx.element_words_mut(index)[0] >> 18 & 0x7ff }
+    }
+
+    #[test]
+    fn batch_fills_free_buffers_partially() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(2, 2);
+        preset(registers::<Fake>().txbrp.as_ptr(), 1 << 4 | 1 << 5);
+
+        let messages = [classic(1), classic(2), classic(3)];
+        assert!(matches!(tx.transmit_batch(&messages), Ok(2)));
+        assert_eq!(registers::<Fake>().txbar.read().bits(), 1 << 2 | 1 << 3);
+        assert_eq!(queued_id(&mut tx, 2), 1);
+        assert_eq!(queued_id(&mut tx, 3), 2);
+    }
+
+    #[test]
+    fn batch_would_block_on_full_queue() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(0, 2);
+
+        assert!(matches!(
+            tx.transmit_batch(&[classic(1)]),
+            Err(nb::Error::WouldBlock)
+        ));
+        assert!(matches!(tx.transmit_batch(&[]), Ok(0)));
+        assert_eq!(registers::<Fake>().txbar.read().bits(), 0);
+    }
+
+    #[test]
+    fn batch_validates_all_messages_first() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(4, 2);
+        let fd = MessageBuilder {
+            id: StandardId::new(2).unwrap().into(),
+            frame_type: tx::FrameType::FlexibleDatarate {
+                payload: &[],
+                bit_rate_switching: false,
+                force_error_state_indicator: false,
+            },
+            store_tx_event: None,
+        }
+        .build()
+        .unwrap();
+
+        assert!(matches!(
+            tx.transmit_batch(&[classic(1), fd]),
+            Err(nb::Error::Other(Error::FdDisabled))
+        ));
+        assert_eq!(registers::<Fake>().txbar.read().bits(), 0);
+        assert_eq!(queued_id(&mut tx, 2), 0);
+    }
+
+    #[test]
+    fn ordered_wraps_around_the_queue() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(3, 5);
+        preset(registers::<Fake>().txbrp.as_ptr(), 1 << 4);
+
+        let messages = [classic(1), classic(2), classic(3)];
+        assert!(matches!(tx.transmit_ordered(&messages), Ok(3)));
+        assert_eq!(
+            registers::<Fake>().txbar.read().bits(),
+            1 << 5 | 1 << 2 | 1 << 3
+        );
+        // The queue is sent starting at the put index
+        assert_eq!(queued_id(&mut tx, 5), 1);
+        assert_eq!(queued_id(&mut tx, 2), 2);
+        assert_eq!(queued_id(&mut tx, 3), 3);
+        assert_eq!(queued_id(&mut tx, 4), 0);
+    }
+
+    #[test]
+    fn ordered_keeps_the_order_in_a_partially_pending_queue() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(2, 3);
+        // Frames queued earlier are still pending before and after the free
+        // buffers
+        preset(registers::<Fake>().txbrp.as_ptr(), 1 << 2 | 1 << 5);
+
+        let messages = [classic(1), classic(2), classic(3)];
+        assert!(matches!(tx.transmit_ordered(&messages), Ok(2)));
+        assert_eq!(registers::<Fake>().txbar.read().bits(), 1 << 3 | 1 << 4);
+        assert_eq!(queued_id(&mut tx, 3), 1);
+        assert_eq!(queued_id(&mut tx, 4), 2);
+    }
+
+    #[test]
+    fn ordered_rejects_priority_queue() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(4, 2);
+        // TFQM
+        preset(registers::<Fake>().txbc.as_ptr(), 1 << 30);
+
+        assert!(matches!(
+            tx.transmit_ordered(&[classic(1)]),
+            Err(nb::Error::Other(Error::QueueNotFifo))
+        ));
+        assert_eq!(registers::<Fake>().txbar.read().bits(), 0);
+        // Unordered batches are fine in a priority queue
+        assert!(matches!(tx.transmit_batch(&[classic(1)]), Ok(1)));
+        assert_eq!(registers::<Fake>().txbar.read().bits(), 1 << 2);
+    }
+
+    #[test]
+    fn retries_would_block_on_full_queue() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(0, 2);
+
+        assert!(matches!(
+            tx.transmit_with_retries(classic(1), 3, 10),
+            Err(nb::Error::WouldBlock)
+        ));
+        assert_eq!(registers::<Fake>().txbar.read().bits(), 0);
+    }
+
+    #[test]
+    fn retries_blocked_after_cancellation() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(4, 2);
+        // The peripheral cancels the first attempt, and the add request of
+        // the buffer is still set when retrying
+        preset(registers::<Fake>().txbcf.as_ptr(), 1 << 2);
+
+        assert!(matches!(
+            tx.transmit_with_retries(classic(1), 3, 10),
+            Err(nb::Error::Other(Error::RetryBlocked { attempts: 1 }))
+        ));
+    }
+
+    #[test]
+    fn blocking_times_out() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(4, 3);
+
+        assert!(matches!(
+            tx.transmit_blocking(classic(1), 10),
+            Err(nb::Error::Other(Error::Timeout))
+        ));
+        // The cancellation of the frame is requested
+        assert_eq!(registers::<Fake>().txbcr.read().bits(), 1 << 3);
+    }
+
+    #[test]
+    fn blocking_reports_bus_off() {
+        fake_peripheral!(Fake);
+        let mut memory = SharedMemory::new();
+        let mut tx = tx::<Fake>(&mut memory);
+        preset_queue::<Fake>(4, 3);
+        // Entering bus off sets INIT
+        preset(registers::<Fake>().cccr.as_ptr(), 1);
+
+        assert!(matches!(
+            tx.transmit_blocking(classic(1), 10),
+            Err(nb::Error::Other(Error::BusOff))
+        ));
+        // The frame remains pending
+        assert_eq!(registers::<Fake>().txbcr.read().bits(), 0);
+    }
+}
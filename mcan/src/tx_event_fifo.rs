@@ -2,7 +2,12 @@
 //!
 //! Events are only generated for messages with [`store_tx_event`] set.
 //!
+//! The queue is disabled if [`Capacities::TxEventFifo`] is zero. It is then
+//! always empty and no events are stored, regardless of the setting of
+//! [`store_tx_event`], see [`DynTxEventFifo::is_enabled`].
+//!
 //! [`store_tx_event`]: crate::message::tx::MessageBuilder::store_tx_event
+//! [`Capacities::TxEventFifo`]: crate::messageram::Capacities::TxEventFifo
 use crate::message::TxEvent;
 use crate::reg;
 use core::marker::PhantomData;
@@ -26,8 +31,38 @@ pub trait DynTxEventFifo {
     fn is_empty(&self) -> bool;
     /// Returns the number of elements the queue can hold
     fn capacity(&self) -> usize;
+    /// Returns `false` if no memory is allocated to the queue, in which case
+    /// it is always empty
+    fn is_enabled(&self) -> bool {
+        self.capacity() != 0
+    }
     /// Takes the first event from the queue
     fn pop(&mut self) -> Option<TxEvent>;
+
+    /// Returns the number of events that can be stored before the queue
+    /// overflows
+    ///
+    /// Transmitting at most this many frames that request an event ensures
+    /// that no completions are lost until the queue is drained again.
+    fn free_space(&self) -> usize {
+        self.capacity().saturating_sub(self.len())
+    }
+
+    /// Takes events from the queue until one with the message marker `marker`
+    /// is found, see [`DynTx::transmit_traced`]. The events taken before it
+    /// are discarded.
+    ///
+    /// Returns `None` if the queue was drained without finding the event.
+    ///
+    /// [`DynTx::transmit_traced`]: crate::tx_buffers::DynTx::transmit_traced
+    fn find(&mut self, marker: u8) -> Option<TxEvent> {
+        while let Some(event) = self.pop() {
+            if event.message_marker() == marker {
+                return Some(event);
+            }
+        }
+        None
+    }
 }
 
 impl<'a, P: mcan_core::CanId> TxEventFifo<'a, P> {
@@ -65,6 +100,9 @@ impl<'a, P: mcan_core::CanId> DynTxEventFifo for TxEventFifo<'a, P> {
     type Id = P;
 
     fn len(&self) -> usize {
+        if !self.is_enabled() {
+            return 0;
+        }
         self.txefs().read().effl().bits() as usize
     }
 
@@ -77,6 +115,9 @@ impl<'a, P: mcan_core::CanId> DynTxEventFifo for TxEventFifo<'a, P> {
     }
 
     fn pop(&mut self) -> Option<TxEvent> {
+        if !self.is_enabled() {
+            return None;
+        }
         let status = self.txefs().read();
         if status.effl().bits() == 0 {
             None
@@ -92,3 +133,27 @@ impl<'a, P: mcan_core::CanId> DynTxEventFifo for TxEventFifo<'a, P> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Disabled;
+    // Safety: The registers are never accessed by a queue without memory
+    unsafe impl mcan_core::CanId for Disabled {
+        const ADDRESS: *const () = core::ptr::null();
+    }
+
+    #[test]
+    fn disabled_queue_is_empty() {
+        // Safety: The registers are not accessed, see above
+        let mut fifo = unsafe { TxEventFifo::<Disabled>::new(&mut []) };
+        assert!(!fifo.is_enabled());
+        assert_eq!(fifo.capacity(), 0);
+        assert_eq!(fifo.len(), 0);
+        assert!(fifo.is_empty());
+        assert_eq!(fifo.free_space(), 0);
+        assert!(fifo.pop().is_none());
+        assert!(fifo.find(0).is_none());
+    }
+}